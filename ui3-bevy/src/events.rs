@@ -0,0 +1,274 @@
+use std::{cell::Cell, cmp::Ordering, rc::Rc, time::Duration};
+
+use bevy::{
+    app::{Events, ManualEventReader},
+    input::{
+        mouse::{MouseButton, MouseScrollUnit, MouseWheel},
+        Input,
+    },
+    math::Vec2,
+    prelude::*,
+    window::Windows,
+};
+use ui3_core::UiBackend;
+
+use crate::{focus::KeyEvent, plugin::UnitKind, prelude::Callback, BevyBackend, UiLocals};
+
+/// Event handlers for [`Unit::Node`](crate::Unit::Node) and
+/// [`Unit::Text`](crate::Unit::Text).
+///
+/// ```ignore
+/// Unit::node(style, color).handlers(Handlers {
+///     on_click: Some(on_click.set(|world, _| ...)),
+///     ..Default::default()
+/// })
+/// ```
+///
+/// Except for hover enter and leave, events start at the topmost unit under
+/// the pointer and bubble up through its ancestors, until a handler calls
+/// [`PointerEvent::stop_propagation`]. Drag events go to the unit the drag
+/// started on, wherever the pointer is. Hover enter and leave are sent to every
+/// unit the pointer enters or leaves, including the ancestors of the topmost
+/// one, and don't bubble.
+///
+/// Key events start at the focused unit, and bubble the same way.
+#[derive(Clone, Default, PartialEq)]
+pub struct Handlers {
+    pub on_hover_enter: Option<Callback<PointerEvent>>,
+    pub on_hover_leave: Option<Callback<PointerEvent>>,
+    pub on_press: Option<Callback<PointerEvent>>,
+    pub on_release: Option<Callback<PointerEvent>>,
+    /// A press and release over the same unit, without dragging in between.
+    pub on_click: Option<Callback<PointerEvent>>,
+    pub on_double_click: Option<Callback<PointerEvent>>,
+    pub on_drag_start: Option<Callback<PointerEvent>>,
+    pub on_drag: Option<Callback<PointerEvent>>,
+    pub on_drag_end: Option<Callback<PointerEvent>>,
+    /// `delta` is the scrolled amount, in pixels.
+    pub on_scroll: Option<Callback<PointerEvent>>,
+    /// Whether the unit can take keyboard focus. Buttons and text inputs
+    /// always can.
+    pub focusable: bool,
+    /// A key pressed while the unit or one of its descendants has focus.
+    pub on_key: Option<Callback<KeyEvent>>,
+}
+
+#[derive(Clone)]
+pub struct PointerEvent {
+    /// The unit the event is about, before bubbling.
+    pub target: Entity,
+    /// The unit whose handler is being called.
+    pub current: Entity,
+    /// The pointer position in window coordinates.
+    pub position: Vec2,
+    /// How far the pointer moved for drags, or was scrolled for scrolls.
+    pub delta: Vec2,
+    stopped: Rc<Cell<bool>>,
+}
+
+impl PointerEvent {
+    /// Keeps the event from bubbling up to the next ancestor.
+    pub fn stop_propagation(&self) {
+        self.stopped.set(true);
+    }
+}
+
+impl Bubble for PointerEvent {
+    fn set_current(&mut self, current: Entity) {
+        self.current = current;
+    }
+
+    fn stopped(&self) -> bool {
+        self.stopped.get()
+    }
+}
+
+/// An event that bubbles up through the ancestors of its target.
+pub(crate) trait Bubble: Clone {
+    fn set_current(&mut self, current: Entity);
+    fn stopped(&self) -> bool;
+}
+
+/// The pointer has to move this far while pressed before it counts as a drag.
+const DRAG_THRESHOLD: f32 = 4.0;
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+// Roughly what a line is on most platforms.
+const SCROLL_LINE_HEIGHT: f32 = 20.0;
+
+#[derive(Default)]
+pub(crate) struct PointerState {
+    position: Option<Vec2>,
+    /// The topmost unit under the pointer and its ancestors.
+    hovered: Vec<Entity>,
+    /// Where the pointer was pressed, and on what.
+    pressed: Option<(Entity, Vec2)>,
+    dragging: bool,
+    last_click: Option<(Entity, Duration)>,
+    wheel: ManualEventReader<MouseWheel>,
+}
+
+impl PointerState {
+    /// The topmost unit under the pointer.
+    pub(crate) fn target(&self) -> Option<Entity> {
+        self.hovered.first().copied()
+    }
+}
+
+/// Sends the pointer events that happened since the last frame.
+pub(crate) fn dispatch_pointer_events(world: &mut World) {
+    let mut state = world.remove_resource::<PointerState>().unwrap_or_default();
+    let position = world
+        .get_resource::<Windows>()
+        .and_then(|windows| windows.get_primary())
+        .and_then(|window| window.cursor_position());
+    let hovered = match position {
+        Some(position) => hit_test(world, position)
+            .map(|target| with_ancestors(world, target))
+            .unwrap_or_default(),
+        None => vec![],
+    };
+    let position = position.or(state.position).unwrap_or_default();
+    let delta = state.position.map_or(Vec2::ZERO, |last| position - last);
+    let event = |target| PointerEvent {
+        target,
+        current: target,
+        position,
+        delta: Vec2::ZERO,
+        stopped: Rc::new(Cell::new(false)),
+    };
+
+    for &entity in state.hovered.iter().filter(|e| !hovered.contains(e)) {
+        send(world, &[entity], event(entity), |h| &h.on_hover_leave);
+    }
+    for &entity in hovered.iter().filter(|e| !state.hovered.contains(e)) {
+        send(world, &[entity], event(entity), |h| &h.on_hover_enter);
+    }
+
+    let (just_pressed, just_released) =
+        world
+            .get_resource::<Input<MouseButton>>()
+            .map_or((false, false), |input| {
+                (
+                    input.just_pressed(MouseButton::Left),
+                    input.just_released(MouseButton::Left),
+                )
+            });
+
+    if let (true, Some(&target)) = (just_pressed, hovered.first()) {
+        send(world, &hovered, event(target), |h| &h.on_press);
+        state.pressed = Some((target, position));
+        state.dragging = false;
+    }
+
+    if let Some((pressed, start)) = state.pressed {
+        let chain = with_ancestors(world, pressed);
+        if !state.dragging && (position - start).length() > DRAG_THRESHOLD {
+            state.dragging = true;
+            send(world, &chain, event(pressed), |h| &h.on_drag_start);
+        }
+        if state.dragging && delta != Vec2::ZERO {
+            let event = PointerEvent {
+                delta,
+                ..event(pressed)
+            };
+            send(world, &chain, event, |h| &h.on_drag);
+        }
+        if just_released {
+            if state.dragging {
+                send(world, &chain, event(pressed), |h| &h.on_drag_end);
+            }
+            if let Some(&target) = hovered.first() {
+                send(world, &hovered, event(target), |h| &h.on_release);
+            }
+            if !state.dragging && hovered.contains(&pressed) {
+                send(world, &chain, event(pressed), |h| &h.on_click);
+                let now = BevyBackend::now(world);
+                match state.last_click {
+                    Some((last, at)) if last == pressed && now - at <= DOUBLE_CLICK_TIME => {
+                        send(world, &chain, event(pressed), |h| &h.on_double_click);
+                        state.last_click = None;
+                    }
+                    _ => state.last_click = Some((pressed, now)),
+                }
+            }
+            state.pressed = None;
+            state.dragging = false;
+        }
+    }
+
+    let scrolled = world
+        .get_resource::<Events<MouseWheel>>()
+        .map_or(Vec2::ZERO, |events| {
+            state
+                .wheel
+                .iter(events)
+                .map(|wheel| match wheel.unit {
+                    MouseScrollUnit::Line => Vec2::new(wheel.x, wheel.y) * SCROLL_LINE_HEIGHT,
+                    MouseScrollUnit::Pixel => Vec2::new(wheel.x, wheel.y),
+                })
+                .fold(Vec2::ZERO, |a, b| a + b)
+        });
+    if let (true, Some(&target)) = (scrolled != Vec2::ZERO, hovered.first()) {
+        let event = PointerEvent {
+            delta: scrolled,
+            ..event(target)
+        };
+        send(world, &hovered, event, |h| &h.on_scroll);
+    }
+
+    state.position = Some(position);
+    state.hovered = hovered;
+    world.insert_resource(state);
+}
+
+/// The topmost unit at `position`, whether it has handlers or not, so units
+/// without handlers still block the ones below them.
+fn hit_test(world: &mut World, position: Vec2) -> Option<Entity> {
+    let mut query = world.query_filtered::<(Entity, &Node, &GlobalTransform), With<UnitKind>>();
+    query
+        .iter(world)
+        .filter(|(_, node, transform)| {
+            let center = transform.translation.truncate();
+            let min = center - node.size / 2.0;
+            let max = center + node.size / 2.0;
+            (min.x..max.x).contains(&position.x) && (min.y..max.y).contains(&position.y)
+        })
+        .max_by(|(_, _, a), (_, _, b)| {
+            a.translation
+                .z
+                .partial_cmp(&b.translation.z)
+                .unwrap_or(Ordering::Equal)
+        })
+        .map(|(entity, _, _)| entity)
+}
+
+pub(crate) fn with_ancestors(world: &World, entity: Entity) -> Vec<Entity> {
+    let mut chain = vec![entity];
+    while let Some(parent) = world.get::<Parent>(*chain.last().unwrap()) {
+        chain.push(parent.0);
+    }
+    chain
+}
+
+/// Calls the handler picked by `handler` on each entity of `chain` in order,
+/// until one stops the propagation.
+pub(crate) fn send<E: Bubble + 'static>(
+    world: &mut World,
+    chain: &[Entity],
+    mut event: E,
+    handler: fn(&Handlers) -> &Option<Callback<E>>,
+) {
+    for &entity in chain {
+        let callback = world
+            .get_non_send_resource::<UiLocals>()
+            .and_then(|locals| locals.handlers.get(&entity))
+            .and_then(|handlers| handler(handlers).clone());
+        if let Some(callback) = callback {
+            event.set_current(entity);
+            callback.call(world, event.clone());
+            if event.stopped() {
+                break;
+            }
+        }
+    }
+}
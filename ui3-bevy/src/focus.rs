@@ -0,0 +1,155 @@
+use std::{cell::Cell, collections::HashMap, rc::Rc};
+
+use bevy::{
+    app::{Events, ManualEventReader},
+    input::{keyboard::KeyboardInput, ElementState, Input},
+    prelude::*,
+};
+
+use ui3_core::{FocusOrder, UnitId};
+
+use crate::{
+    events::{send, with_ancestors, Bubble, PointerState},
+    UiLocals,
+};
+
+/// Keyboard focus, read and moved by widgets through
+/// [`Focus`](ui3_core::Focus).
+///
+/// Tab and shift-tab move it through the focusable units in render order, and
+/// clicking a unit focuses it or its closest focusable ancestor. Every other
+/// key press goes to the focused unit's `on_key` handler and bubbles up from
+/// there, see [`Handlers`](crate::Handlers). Return and space also click a
+/// focused button.
+#[derive(Default)]
+pub(crate) struct UiFocus {
+    pub(crate) focused: Option<Entity>,
+    /// As of the last render.
+    pub(crate) order: FocusOrder,
+    /// The entities of the units in `order`.
+    pub(crate) entities: HashMap<UnitId, Entity>,
+    keys: ManualEventReader<KeyboardInput>,
+}
+
+#[derive(Clone)]
+pub struct KeyEvent {
+    /// The focused unit.
+    pub target: Entity,
+    /// The unit whose handler is being called.
+    pub current: Entity,
+    pub key: KeyCode,
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    stopped: Rc<Cell<bool>>,
+}
+
+impl UiFocus {
+    fn unit_of(&self, entity: Entity) -> Option<UnitId> {
+        self.entities
+            .iter()
+            .find(|(_, &e)| e == entity)
+            .map(|(&id, _)| id)
+    }
+}
+
+impl KeyEvent {
+    /// Keeps the event from bubbling up to the next ancestor.
+    pub fn stop_propagation(&self) {
+        self.stopped.set(true);
+    }
+}
+
+impl Bubble for KeyEvent {
+    fn set_current(&mut self, current: Entity) {
+        self.current = current;
+    }
+
+    fn stopped(&self) -> bool {
+        self.stopped.get()
+    }
+}
+
+/// Moves the focus for clicks and tabs, and sends the other key presses since
+/// the last frame to the focused unit.
+pub(crate) fn dispatch_focus_events(world: &mut World) {
+    let mut focus = world.get_resource_or_insert_with(UiFocus::default);
+    // Read even without a focused unit, so old key presses don't show up
+    // once one is focused.
+    let mut reader = std::mem::take(&mut focus.keys);
+    let pressed: Vec<_> = world
+        .get_resource::<Events<KeyboardInput>>()
+        .map(|events| {
+            reader
+                .iter(events)
+                .filter(|key| key.state == ElementState::Pressed)
+                .filter_map(|key| key.key_code)
+                .collect()
+        })
+        .unwrap_or_default();
+    world.get_resource_mut::<UiFocus>().unwrap().keys = reader;
+
+    let clicked = world
+        .get_resource::<Input<MouseButton>>()
+        .map_or(false, |input| input.just_pressed(MouseButton::Left));
+    if clicked {
+        let target = world
+            .get_resource::<PointerState>()
+            .and_then(PointerState::target);
+        let chain = target.map_or(vec![], |target| with_ancestors(world, target));
+        let mut focus = world.get_resource_mut::<UiFocus>().unwrap();
+        focus.focused = chain.into_iter().find(|&e| focus.unit_of(e).is_some());
+    }
+
+    let (shift, control, alt) =
+        world
+            .get_resource::<Input<KeyCode>>()
+            .map_or((false, false, false), |input| {
+                (
+                    input.pressed(KeyCode::LShift) || input.pressed(KeyCode::RShift),
+                    input.pressed(KeyCode::LControl) || input.pressed(KeyCode::RControl),
+                    input.pressed(KeyCode::LAlt) || input.pressed(KeyCode::RAlt),
+                )
+            });
+    for key in pressed {
+        let mut focus = world.get_resource_mut::<UiFocus>().unwrap();
+        if key == KeyCode::Tab {
+            let current = focus.focused.and_then(|e| focus.unit_of(e));
+            let next = if shift {
+                focus.order.prev(current)
+            } else {
+                focus.order.next(current)
+            };
+            focus.focused = next.and_then(|id| focus.entities.get(&id).copied());
+            continue;
+        }
+        press_key(world, key, shift, control, alt);
+    }
+}
+
+/// Sends a key press to the focused unit. Return and space also click it if
+/// it's a button.
+pub(crate) fn press_key(world: &mut World, key: KeyCode, shift: bool, control: bool, alt: bool) {
+    let target = match world.get_resource::<UiFocus>().and_then(|f| f.focused) {
+        Some(target) => target,
+        None => return,
+    };
+    let button = world
+        .get_non_send_resource::<UiLocals>()
+        .and_then(|locals| locals.buttons.get(&target))
+        .map(|button| button.func.clone());
+    if let (KeyCode::Return | KeyCode::Space, Some(func)) = (key, button) {
+        func.call(world, ());
+    }
+    let event = KeyEvent {
+        target,
+        current: target,
+        key,
+        shift,
+        control,
+        alt,
+        stopped: Rc::new(Cell::new(false)),
+    };
+    let chain = with_ancestors(world, target);
+    send(world, &chain, event, |h| &h.on_key);
+}
@@ -0,0 +1,453 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::HandleId,
+    log::warn,
+    math::{Rect, Vec2},
+    prelude::*,
+    render::texture::{Extent3d, TextureDimension, TextureFormat},
+    sprite::Rect as TextureRect,
+};
+
+use crate::{
+    material::{new_material, release_material, update_material},
+    plugin::set_children,
+};
+
+/// How a [`Unit::Image`](crate::Unit::Image) fits its texture into its node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageMode {
+    /// Scales the texture to the node's size, distorting it if their aspect
+    /// ratios differ.
+    Stretch,
+    /// Keeps the node at the texture's aspect ratio, as large as its style
+    /// allows.
+    Fit,
+    /// Crops the texture to the node's aspect ratio, so it covers the whole
+    /// node without being distorted.
+    Fill,
+}
+
+impl Default for ImageMode {
+    fn default() -> Self {
+        ImageMode::Stretch
+    }
+}
+
+pub(crate) struct ImageData {
+    image: Handle<Texture>,
+    color: Color,
+    mode: ImageMode,
+    rect: Option<TextureRect>,
+    slice: Option<Rect<f32>>,
+    /// The aspect ratio of the shown part of the texture, once it's loaded.
+    aspect: Option<f32>,
+    /// The node size the image was last built for, `None` if it has to be
+    /// built again.
+    built: Option<Vec2>,
+    /// The nodes showing the nine parts of a nine-slice image.
+    pub(crate) parts: Vec<Entity>,
+    /// The crops the image is showing, released once it's built again.
+    crops: Vec<CropKey>,
+}
+
+impl ImageData {
+    pub(crate) fn new(
+        image: &Handle<Texture>,
+        color: Color,
+        mode: ImageMode,
+        rect: &Option<TextureRect>,
+        slice: &Option<Rect<f32>>,
+    ) -> Self {
+        Self {
+            image: image.clone(),
+            color,
+            mode,
+            rect: *rect,
+            slice: *slice,
+            aspect: None,
+            built: None,
+            parts: vec![],
+            crops: vec![],
+        }
+    }
+
+    /// Takes the props of a re-rendered unit, so the image is built again if
+    /// they changed.
+    pub(crate) fn update(
+        &mut self,
+        image: &Handle<Texture>,
+        color: Color,
+        mode: ImageMode,
+        rect: &Option<TextureRect>,
+        slice: &Option<Rect<f32>>,
+    ) {
+        let rect_eq = match (&self.rect, rect) {
+            (Some(a), Some(b)) => a.min == b.min && a.max == b.max,
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        if self.image != *image || !rect_eq {
+            self.aspect = None;
+        }
+        if self.image != *image
+            || self.color != color
+            || self.mode != mode
+            || !rect_eq
+            || self.slice != *slice
+        {
+            self.image = image.clone();
+            self.color = color;
+            self.mode = mode;
+            self.rect = *rect;
+            self.slice = *slice;
+            self.built = None;
+        }
+    }
+
+    /// `style`, keeping the aspect ratio of the texture for
+    /// [`ImageMode::Fit`].
+    pub(crate) fn style(&self, style: &Style) -> Style {
+        match self.aspect {
+            Some(aspect) if self.mode == ImageMode::Fit && self.slice.is_none() => Style {
+                aspect_ratio: Some(aspect),
+                ..style.clone()
+            },
+            _ => style.clone(),
+        }
+    }
+}
+
+/// Builds the images whose props or size changed since the last frame. Has to
+/// run after the layout of the last frame, and retries every frame until the
+/// texture is loaded.
+pub(crate) fn update_images(world: &mut World) {
+    let mut query = world.query::<(Entity, &ImageData, &Node)>();
+    let stale: Vec<_> = query
+        .iter(world)
+        .filter(|(_, data, node)| match data.built {
+            Some(size) => {
+                size != node.size && (data.mode == ImageMode::Fill || data.slice.is_some())
+            }
+            None => true,
+        })
+        .map(|(entity, _, node)| (entity, node.size))
+        .collect();
+    for (entity, size) in stale {
+        build_image(world, entity, size);
+    }
+}
+
+fn build_image(world: &mut World, entity: Entity, size: Vec2) {
+    let data = world.get::<ImageData>(entity).unwrap();
+    let (image, color, mode, slice) = (data.image.clone(), data.color, data.mode, data.slice);
+    // Otherwise only the size changed.
+    let changed = data.built.is_none();
+    let full = match world.get_resource::<Assets<Texture>>().unwrap().get(&image) {
+        Some(texture) => TextureRect {
+            min: Vec2::ZERO,
+            max: Vec2::new(texture.size.width as f32, texture.size.height as f32),
+        },
+        None => return,
+    };
+    let source = data.rect.unwrap_or(full);
+    let source_size = source.max - source.min;
+
+    let mut data = world.get_mut::<ImageData>(entity).unwrap();
+    data.built = Some(size);
+    if data.aspect.is_none() && source_size.y > 0.0 {
+        data.aspect = Some(source_size.x / source_size.y);
+        if mode == ImageMode::Fit && slice.is_none() {
+            let aspect = data.aspect;
+            world.get_mut::<Style>(entity).unwrap().aspect_ratio = aspect;
+        }
+    }
+
+    match slice {
+        Some(slice) => {
+            let textures = if changed {
+                update_material(world, entity, Color::NONE, &None);
+                let (crops, textures): (Vec<_>, Vec<_>) =
+                    slice_textures(world, &image, source, slice)
+                        .into_iter()
+                        .map(|crop| match crop {
+                            Some((key, texture)) => (Some(key), Some(texture)),
+                            None => (None, None),
+                        })
+                        .unzip();
+                set_crops(world, entity, crops.into_iter().flatten().collect());
+                Some(textures)
+            } else {
+                None
+            };
+            layout_slices(world, entity, textures, color, slice, size);
+        }
+        None => {
+            let parts = std::mem::take(&mut world.get_mut::<ImageData>(entity).unwrap().parts);
+            parts.into_iter().for_each(|part| {
+                release_material(world, part);
+                world.despawn(part);
+            });
+            let shown = match mode {
+                ImageMode::Fill if size.x > 0.0 && size.y > 0.0 => cover(source, size),
+                _ => source,
+            };
+            let (crops, texture) = if shown.min == full.min && shown.max == full.max {
+                (vec![], Some(image))
+            } else {
+                match crop(world, &image, shown) {
+                    Some((key, texture)) => (vec![key], Some(texture)),
+                    None => (vec![], None),
+                }
+            };
+            update_material(world, entity, color, &texture);
+            set_crops(world, entity, crops);
+        }
+    }
+}
+
+/// Cuts the nine parts of a nine-slice image out of `source`, row by row.
+fn slice_textures(
+    world: &mut World,
+    image: &Handle<Texture>,
+    source: TextureRect,
+    slice: Rect<f32>,
+) -> Vec<Option<(CropKey, Handle<Texture>)>> {
+    let xs = [
+        source.min.x,
+        source.min.x + slice.left,
+        source.max.x - slice.right,
+        source.max.x,
+    ];
+    // Texture rows go top to bottom.
+    let ys = [
+        source.min.y,
+        source.min.y + slice.top,
+        source.max.y - slice.bottom,
+        source.max.y,
+    ];
+    let mut textures = vec![];
+    for row in 0..3 {
+        for column in 0..3 {
+            let rect = TextureRect {
+                min: Vec2::new(xs[column], ys[row]),
+                max: Vec2::new(xs[column + 1], ys[row + 1]),
+            };
+            textures.push(crop(world, image, rect));
+        }
+    }
+    textures
+}
+
+/// Lays out the nine parts of a nine-slice image: the corners keep their
+/// size, the edges stretch along one axis and the center along both. New
+/// `textures` are applied to the parts, which are spawned if they're missing.
+fn layout_slices(
+    world: &mut World,
+    entity: Entity,
+    textures: Option<Vec<Option<Handle<Texture>>>>,
+    color: Color,
+    slice: Rect<f32>,
+    size: Vec2,
+) {
+    let right = (size.x - slice.right).max(slice.left);
+    let bottom = (size.y - slice.bottom).max(slice.top);
+    let xs = [0.0, slice.left, right, right + slice.right];
+    let ys = [0.0, slice.top, bottom, bottom + slice.bottom];
+
+    let mut parts = std::mem::take(&mut world.get_mut::<ImageData>(entity).unwrap().parts);
+    for row in 0..3 {
+        for column in 0..3 {
+            let index = row * 3 + column;
+            let style = Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(xs[column]),
+                    top: Val::Px(ys[row]),
+                    ..Default::default()
+                },
+                size: Size::new(
+                    Val::Px(xs[column + 1] - xs[column]),
+                    Val::Px(ys[row + 1] - ys[row]),
+                ),
+                ..Default::default()
+            };
+            let texture = textures.as_ref().map(|textures| &textures[index]);
+            let color = |texture: &Option<_>| {
+                if texture.is_some() {
+                    color
+                } else {
+                    Color::NONE
+                }
+            };
+            match parts.get(index) {
+                Some(&part) => {
+                    *world.get_mut::<Style>(part).unwrap() = style;
+                    if let Some(texture) = texture {
+                        update_material(world, part, color(texture), texture);
+                    }
+                }
+                None => {
+                    let texture = texture.cloned().flatten();
+                    let material = new_material(world, color(&texture), &texture);
+                    let part = world
+                        .spawn()
+                        .insert_bundle(NodeBundle {
+                            style,
+                            material,
+                            ..Default::default()
+                        })
+                        .id();
+                    parts.push(part);
+                }
+            }
+        }
+    }
+
+    // Drawn below the unit's children.
+    let children = world
+        .get::<Children>(entity)
+        .map_or(vec![], |children| children.to_vec());
+    let mut ordered = parts.clone();
+    ordered.extend(children.into_iter().filter(|child| !parts.contains(child)));
+    set_children(world, entity, &ordered);
+    world.get_mut::<ImageData>(entity).unwrap().parts = parts;
+}
+
+/// The part of `source` with the aspect ratio of `size`, around its center.
+fn cover(source: TextureRect, size: Vec2) -> TextureRect {
+    let source_size = source.max - source.min;
+    let scale = (size.x / source_size.x).max(size.y / source_size.y);
+    let center = (source.min + source.max) / 2.0;
+    let shown = size / scale;
+    TextureRect {
+        min: center - shown / 2.0,
+        max: center + shown / 2.0,
+    }
+}
+
+/// The texture and the pixel columns and rows `[x0, x1, y0, y1]` of a crop.
+type CropKey = (HandleId, [usize; 4]);
+
+/// The textures cut out of other textures, shared between the images showing
+/// the same part of the same texture instead of being cut again each time
+/// they're built.
+///
+/// Each crop counts the images using it, and is dropped from the cache once
+/// none are left.
+#[derive(Default)]
+pub(crate) struct CropCache(HashMap<CropKey, (Handle<Texture>, usize)>);
+
+/// Whether the pixels of `texture` can be copied row by row: a 2D color
+/// texture whose data holds all of its pixels.
+fn croppable(texture: &Texture) -> bool {
+    let depth = matches!(
+        texture.format,
+        TextureFormat::Depth32Float
+            | TextureFormat::Depth24Plus
+            | TextureFormat::Depth24PlusStencil8
+    );
+    let len =
+        texture.size.width as usize * texture.size.height as usize * texture.format.pixel_size();
+    !depth
+        && texture.dimension == TextureDimension::D2
+        && texture.size.depth == 1
+        && texture.data.len() >= len
+}
+
+/// Gets a texture with `rect` of `image` from the cache, copying it into a new
+/// one if it's missing, to be released with [`set_crops`] or
+/// [`release_image`]. Returns `None` if `rect` is empty or the texture's
+/// format can't be cropped, which is logged.
+fn crop(
+    world: &mut World,
+    image: &Handle<Texture>,
+    rect: TextureRect,
+) -> Option<(CropKey, Handle<Texture>)> {
+    let textures = world.get_resource::<Assets<Texture>>().unwrap();
+    let texture = textures.get(image)?;
+    if !croppable(texture) {
+        warn!(
+            "can't crop a {:?} texture with {:?} format",
+            texture.dimension, texture.format
+        );
+        return None;
+    }
+    let (width, height) = (texture.size.width as usize, texture.size.height as usize);
+    let x = |x: f32| (x.round().max(0.0) as usize).min(width);
+    let y = |y: f32| (y.round().max(0.0) as usize).min(height);
+    let (x0, x1, y0, y1) = (x(rect.min.x), x(rect.max.x), y(rect.min.y), y(rect.max.y));
+    if x1 <= x0 || y1 <= y0 {
+        return None;
+    }
+    let key = (image.id, [x0, x1, y0, y1]);
+
+    let mut cache = world.remove_resource::<CropCache>().unwrap_or_default();
+    let handle = match cache.0.get_mut(&key) {
+        Some((handle, count)) => {
+            *count += 1;
+            handle.clone()
+        }
+        None => {
+            let mut textures = world.get_resource_mut::<Assets<Texture>>().unwrap();
+            let texture = textures.get(image).unwrap();
+            let pixel = texture.format.pixel_size();
+            let mut data = Vec::with_capacity((x1 - x0) * (y1 - y0) * pixel);
+            for row in y0..y1 {
+                let start = row * width * pixel;
+                data.extend_from_slice(&texture.data[start + x0 * pixel..start + x1 * pixel]);
+            }
+            let mut cropped = Texture::new(
+                Extent3d::new((x1 - x0) as u32, (y1 - y0) as u32, 1),
+                TextureDimension::D2,
+                data,
+                texture.format,
+            );
+            cropped.sampler = texture.sampler.clone();
+            let handle = textures.add(cropped);
+            cache.0.insert(key, (handle.clone(), 1));
+            handle
+        }
+    };
+    world.insert_resource(cache);
+    Some((key, handle))
+}
+
+/// Switches the crops `entity` is showing to `crops`, releasing the old ones.
+fn set_crops(world: &mut World, entity: Entity, crops: Vec<CropKey>) {
+    let old = std::mem::replace(
+        &mut world.get_mut::<ImageData>(entity).unwrap().crops,
+        crops,
+    );
+    release_crops(world, old);
+}
+
+fn release_crops(world: &mut World, crops: Vec<CropKey>) {
+    if crops.is_empty() {
+        return;
+    }
+    let mut cache = world.get_resource_mut::<CropCache>().unwrap();
+    for key in crops {
+        if let Some((_, count)) = cache.0.get_mut(&key) {
+            *count -= 1;
+            if *count == 0 {
+                cache.0.remove(&key);
+            }
+        }
+    }
+}
+
+/// Despawns the parts of the image on `entity` and gives back its crops,
+/// before it's despawned.
+pub(crate) fn release_image(world: &mut World, entity: Entity) {
+    let mut data = match world.get_mut::<ImageData>(entity) {
+        Some(data) => data,
+        None => return,
+    };
+    let parts = std::mem::take(&mut data.parts);
+    let crops = std::mem::take(&mut data.crops);
+    for part in parts {
+        release_material(world, part);
+        world.despawn(part);
+    }
+    release_crops(world, crops);
+}
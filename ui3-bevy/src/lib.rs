@@ -1,29 +1,153 @@
 #![feature(generic_associated_types)]
 
-use std::rc::Rc;
+mod events;
+mod focus;
+mod image;
+mod material;
+mod navigation;
+mod plugin;
+mod rich_text;
+mod text_input;
+
+pub use events::{Handlers, PointerEvent};
+pub use focus::KeyEvent;
+pub use image::ImageMode;
+pub use plugin::{Ui3Plugin, UiFont};
+pub use rich_text::{MarkupError, RichText};
+pub use text_input::TextInputValue;
+pub use ui3_core;
+
+/// [`ui3_core::view!`] with its paths resolved through this crate, so it
+/// works without a direct dependency on `ui3_core`.
+#[macro_export]
+macro_rules! view {
+    ($($tt:tt)*) => {
+        $crate::ui3_core::view! { crate = $crate::ui3_core; $($tt)* }
+    };
+}
+
+use std::{any::Any, collections::HashMap, marker::PhantomData, rc::Rc, time::Duration};
 
 use bevy::{
+    core::Time,
     ecs::prelude::*,
+    math::Rect,
     prelude::{Color, Handle, Texture},
-    text::Text,
-    ui::Style,
+    sprite::{self, TextureAtlas},
+    text::{Text, TextStyle},
+    ui::{Interaction, Style},
 };
-use ui3_core::{UiBackend, WidgetParam};
+use focus::UiFocus;
+use plugin::ButtonCallbacks;
+use ui3_core::{Callback, SetState, StateSlots, UiBackend, WidgetParam};
 
 pub struct BevyBackend;
 
 #[repr(transparent)]
 struct UiStoreWrapper<T>(T);
 
+/// What the UI keeps that isn't `Send`: the values of local stores, keyed by
+/// their store's entity, the callbacks of units, keyed by the unit's entity,
+/// and the slots of [`State`](ui3_core::State) params.
+///
+/// It's kept in a non-send resource instead of on the entities, which would
+/// drop it on whichever thread despawns them or drops the world. The entities
+/// only provide the ids.
+#[derive(Default)]
+pub(crate) struct UiLocals {
+    stores: HashMap<Entity, LocalStoreEntry>,
+    pub(crate) handlers: HashMap<Entity, Handlers>,
+    pub(crate) buttons: HashMap<Entity, ButtonCallbacks>,
+    pub(crate) text_inputs: HashMap<Entity, Callback<BevyBackend, TextInputValue>>,
+    state_slots: StateSlots,
+}
+
+struct LocalStoreEntry {
+    value: Box<dyn Any>,
+    changed: u32,
+}
+
+impl UiLocals {
+    /// The resource, inserted first if it's missing.
+    pub(crate) fn get_or_insert(world: &mut World) -> Mut<'_, Self> {
+        if world.get_non_send_resource::<Self>().is_none() {
+            world.insert_non_send(Self::default());
+        }
+        world.get_non_send_resource_mut().unwrap()
+    }
+
+    fn entry(&self, id: Entity) -> &LocalStoreEntry {
+        &self.stores[&id]
+    }
+
+    /// Drops the callbacks of the unit rendered by `entity`.
+    pub(crate) fn remove_unit(&mut self, entity: Entity) {
+        self.handlers.remove(&entity);
+        self.buttons.remove(&entity);
+        self.text_inputs.remove(&entity);
+    }
+}
+
+pub struct LocalMut<'a, T: 'static> {
+    locals: Mut<'a, UiLocals>,
+    id: Entity,
+    change_tick: u32,
+    marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T: 'static> std::ops::Deref for LocalMut<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.locals.entry(self.id).value.downcast_ref().unwrap()
+    }
+}
+
+impl<'a, T: 'static> std::ops::DerefMut for LocalMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        let entry = self.locals.stores.get_mut(&self.id).unwrap();
+        entry.changed = self.change_tick;
+        entry.value.downcast_mut().unwrap()
+    }
+}
+
 impl UiBackend for BevyBackend {
     type Unit = Unit;
 
     type RunCtx<'a> = World;
 
+    type NodeId = Entity;
+
     fn mark_update(ctx: &mut Self::RunCtx<'_>) {
         ctx.increment_change_tick();
     }
 
+    fn now(ctx: &Self::RunCtx<'_>) -> Duration {
+        ctx.get_resource::<Time>()
+            .map_or(Duration::default(), Time::time_since_startup)
+    }
+
+    fn is_focusable(unit: &Self::Unit) -> bool {
+        match unit {
+            Unit::Node { handlers, .. } | Unit::Text { handlers, .. } => handlers.focusable,
+            Unit::Button { .. } | Unit::TextInput { .. } => true,
+            Unit::Image { .. } => false,
+        }
+    }
+
+    fn focused(ctx: &Self::RunCtx<'_>) -> Option<Self::NodeId> {
+        ctx.get_resource::<UiFocus>()
+            .and_then(|focus| focus.focused)
+    }
+
+    fn set_focused(ctx: &mut Self::RunCtx<'_>, id: Option<Self::NodeId>) {
+        ctx.get_resource_or_insert_with(UiFocus::default).focused = id;
+    }
+
+    fn with_state_slots<R>(ctx: &mut Self::RunCtx<'_>, f: impl FnOnce(&mut StateSlots) -> R) -> R {
+        f(&mut UiLocals::get_or_insert(ctx).state_slots)
+    }
+
     type StoreId = Entity;
 
     type TrackingPtr<'a, T: Send + Sync + 'static> = Mut<'a, T>;
@@ -65,6 +189,9 @@ impl UiBackend for BevyBackend {
 
     fn deinit_store(data: Self::StoreInitData, ctx: &mut Self::RunCtx<'_>) {
         ctx.despawn(data.0);
+        if let Some(mut locals) = ctx.get_non_send_resource_mut::<UiLocals>() {
+            locals.stores.remove(&data.0);
+        }
     }
 
     fn id_from_store_init_data(data: &Self::StoreInitData) -> Self::StoreId {
@@ -80,17 +207,83 @@ impl UiBackend for BevyBackend {
             .unwrap()
             .is_changed(init_data.1, ctx.read_change_tick())
     }
+
+    type LocalTrackingPtr<'a, T: 'static> = LocalMut<'a, T>;
+
+    fn access_local_store_mut<'a, T: 'static>(
+        id: Self::StoreId,
+        ctx: &'a mut Self::RunCtx<'_>,
+    ) -> Self::LocalTrackingPtr<'a, T> {
+        let change_tick = ctx.read_change_tick();
+        LocalMut {
+            locals: ctx.get_non_send_resource_mut().unwrap(),
+            id,
+            change_tick,
+            marker: PhantomData,
+        }
+    }
+
+    fn access_local_store<'a, T: 'static>(id: Self::StoreId, ctx: &'a Self::RunCtx<'_>) -> &'a T {
+        ctx.get_non_send_resource::<UiLocals>()
+            .unwrap()
+            .entry(id)
+            .value
+            .downcast_ref()
+            .unwrap()
+    }
+
+    fn read_local_store_marked<'a, T: 'static>(
+        id: Self::StoreId,
+        ctx: &'a Self::RunCtx<'_>,
+        init_data: &mut Self::StoreInitData,
+    ) -> &'a T {
+        init_data.1 = ctx.read_change_tick();
+        Self::access_local_store(id, ctx)
+    }
+
+    fn init_local_store<T: 'static>(ctx: &mut Self::RunCtx<'_>, val: T) -> Self::StoreInitData {
+        let id = ctx.spawn().id();
+        let entry = LocalStoreEntry {
+            value: Box::new(val),
+            changed: ctx.read_change_tick(),
+        };
+        UiLocals::get_or_insert(ctx).stores.insert(id, entry);
+        (id, 0)
+    }
+
+    fn check_local_store_needs_recalc<T: 'static>(
+        ctx: &Self::RunCtx<'_>,
+        init_data: &Self::StoreInitData,
+    ) -> bool {
+        // The same comparison bevy's `ComponentTicks::is_changed` makes.
+        let change_tick = ctx.read_change_tick();
+        let changed = ctx
+            .get_non_send_resource::<UiLocals>()
+            .unwrap()
+            .entry(init_data.0)
+            .changed;
+        change_tick.wrapping_sub(changed) < change_tick.wrapping_sub(init_data.1)
+    }
 }
 
 pub mod prelude {
     use super::BevyBackend;
 
-    pub use ui3_core::WidgetFunc;
+    pub use crate::view;
+    pub use ui3_core::{component, WidgetFunc};
 
     pub type UiApp = ui3_core::Application<BevyBackend>;
     pub type WidgetNode = ui3_core::WidgetNode<BevyBackend>;
+    pub type Callback<Args = ()> = ui3_core::Callback<BevyBackend, Args>;
+    pub type UseCallback<'s, Args = ()> = ui3_core::UseCallback<'s, BevyBackend, Args>;
+    pub type Store<'a, T> = ui3_core::Store<'a, T, BevyBackend>;
+    pub type LocalStore<'a, T> = ui3_core::LocalStore<'a, T, BevyBackend>;
+    pub type State<'s, T> = ui3_core::State<'s, T, BevyBackend>;
+    pub type SetState<T> = ui3_core::SetState<T, BevyBackend>;
+    pub type NodeRef = ui3_core::NodeRef<BevyBackend>;
+    pub type Focus = ui3_core::Focus<BevyBackend>;
 
-    pub use crate::UiRes;
+    pub use crate::{Ui3Plugin, UiFont, UiRes};
 }
 
 #[derive(Clone)]
@@ -99,9 +292,13 @@ pub enum Unit {
         style: Style,
         color: Color,
         image: Option<Handle<Texture>>,
+        handlers: Handlers,
     },
     Button {
-        func: Rc<dyn Fn(&mut World)>,
+        /// Called when the button is released while hovered.
+        func: Callback<BevyBackend>,
+        /// Kept up to date with the button's [`ButtonState`].
+        state: Option<SetState<ButtonState, BevyBackend>>,
         style: Style,
         color: Color,
         image: Option<Handle<Texture>>,
@@ -109,7 +306,181 @@ pub enum Unit {
     Text {
         style: Style,
         text: Text,
+        handlers: Handlers,
     },
+    /// A single line of editable text, typed into while it has focus;
+    /// edits aren't applied to `value` directly but passed to `on_change`,
+    /// which usually writes them to a store that renders the input again.
+    TextInput {
+        value: TextInputValue,
+        /// Shown, dimmed, while the value is empty and the input isn't focused.
+        placeholder: String,
+        style: Style,
+        text_style: TextStyle,
+        on_change: Callback<BevyBackend, TextInputValue>,
+    },
+    /// A texture, or a part of one such as a sprite of an atlas.
+    Image {
+        style: Style,
+        image: Handle<Texture>,
+        /// Multiplied with the texture's colors.
+        color: Color,
+        mode: ImageMode,
+        /// The part of the texture to show, in pixels from its top left
+        /// corner. The whole texture if `None`.
+        rect: Option<sprite::Rect>,
+        /// Nine-slice borders, in pixels: the corners keep their size and the
+        /// edges only stretch along them, so frames of panels and buttons
+        /// don't distort. `mode` doesn't apply to nine-slice images.
+        slice: Option<Rect<f32>>,
+    },
+}
+
+impl Unit {
+    pub fn node(style: Style, color: Color) -> Self {
+        Unit::Node {
+            style,
+            color,
+            image: None,
+            handlers: Handlers::default(),
+        }
+    }
+
+    pub fn button(func: Callback<BevyBackend>, style: Style, color: Color) -> Self {
+        Unit::Button {
+            func,
+            state: None,
+            style,
+            color,
+            image: None,
+        }
+    }
+
+    pub fn text(text: Text) -> Self {
+        Unit::Text {
+            style: Default::default(),
+            text,
+            handlers: Handlers::default(),
+        }
+    }
+
+    pub fn text_input(
+        value: TextInputValue,
+        on_change: Callback<BevyBackend, TextInputValue>,
+        text_style: TextStyle,
+    ) -> Self {
+        Unit::TextInput {
+            value,
+            placeholder: String::new(),
+            style: Default::default(),
+            text_style,
+            on_change,
+        }
+    }
+
+    pub fn image(image: Handle<Texture>) -> Self {
+        Unit::Image {
+            style: Default::default(),
+            image,
+            color: Color::WHITE,
+            mode: ImageMode::default(),
+            rect: None,
+            slice: None,
+        }
+    }
+
+    /// The sprite at `index` in `atlas`.
+    pub fn atlas_image(atlas: &TextureAtlas, index: usize) -> Self {
+        Unit::Image {
+            rect: Some(atlas.textures[index]),
+            ..Unit::image(atlas.texture.clone())
+        }
+    }
+
+    /// Sets how an image unit fits its node, other units are returned
+    /// unchanged.
+    pub fn image_mode(mut self, new: ImageMode) -> Self {
+        if let Unit::Image { mode, .. } = &mut self {
+            *mode = new;
+        }
+        self
+    }
+
+    /// Makes an image unit nine-slice, other units are returned unchanged.
+    pub fn nine_slice(mut self, borders: Rect<f32>) -> Self {
+        if let Unit::Image { slice, .. } = &mut self {
+            *slice = Some(borders);
+        }
+        self
+    }
+
+    /// Sets the event handlers of a node or text unit, other units
+    /// are returned unchanged.
+    pub fn handlers(mut self, new: Handlers) -> Self {
+        if let Unit::Node { handlers, .. } | Unit::Text { handlers, .. } = &mut self {
+            *handlers = new;
+        }
+        self
+    }
+
+    /// Keeps `setter` up to date with the [`ButtonState`] of a button unit,
+    /// other units are returned unchanged.
+    pub fn button_state(mut self, setter: SetState<ButtonState, BevyBackend>) -> Self {
+        if let Unit::Button { state, .. } = &mut self {
+            *state = Some(setter);
+        }
+        self
+    }
+}
+
+/// A unit without children.
+impl From<Unit> for ui3_core::WidgetNode<BevyBackend> {
+    fn from(unit: Unit) -> Self {
+        ui3_core::WidgetNode::Unit {
+            unit,
+            children: Rc::new(ui3_core::WidgetNode::None),
+        }
+    }
+}
+
+/// How the pointer is interacting with a [`Unit::Button`], for widgets to
+/// restyle it.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use ui3_bevy::{prelude::*, ButtonState, Unit};
+/// fn my_button(on_click: &Callback, state: State<ButtonState>) -> WidgetNode {
+///     let color = match *state {
+///         ButtonState::Idle => Color::GRAY,
+///         ButtonState::Hovered => Color::SILVER,
+///         ButtonState::Pressed => Color::WHITE,
+///     };
+///     view! {
+///         { Unit::button(on_click.clone(), Style::default(), color).button_state(state.setter()) }
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ButtonState {
+    Idle,
+    Hovered,
+    Pressed,
+}
+
+impl Default for ButtonState {
+    fn default() -> Self {
+        ButtonState::Idle
+    }
+}
+
+impl From<Interaction> for ButtonState {
+    fn from(interaction: Interaction) -> Self {
+        match interaction {
+            Interaction::None => ButtonState::Idle,
+            Interaction::Hovered => ButtonState::Hovered,
+            Interaction::Clicked => ButtonState::Pressed,
+        }
+    }
 }
 
 pub struct UiRes<'a, T> {
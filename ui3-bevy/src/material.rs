@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use bevy::{asset::HandleId, prelude::*};
+
+/// The color as bits, since `Color` isn't `Eq`, and the texture.
+type MaterialKey = ([u32; 4], Option<HandleId>);
+
+/// The `ColorMaterial`s of the rendered units, shared between the units that
+/// look the same instead of being added for each of them.
+///
+/// Each material counts the entities using it, and is dropped from the cache
+/// once none are left, which frees it as soon as Bevy is done with it.
+#[derive(Default)]
+pub(crate) struct MaterialCache {
+    materials: HashMap<MaterialKey, (Handle<ColorMaterial>, usize)>,
+    keys: HashMap<HandleId, MaterialKey>,
+}
+
+fn key(color: Color, image: &Option<Handle<Texture>>) -> MaterialKey {
+    let [r, g, b, a] = color.as_rgba_f32();
+    (
+        [r.to_bits(), g.to_bits(), b.to_bits(), a.to_bits()],
+        image.as_ref().map(|image| image.id),
+    )
+}
+
+/// Gets a material for a new entity, to be released along with it.
+pub(crate) fn new_material(
+    world: &mut World,
+    color: Color,
+    image: &Option<Handle<Texture>>,
+) -> Handle<ColorMaterial> {
+    let key = key(color, image);
+    let mut cache = world.remove_resource::<MaterialCache>().unwrap_or_default();
+    let handle = match cache.materials.get_mut(&key) {
+        Some((handle, count)) => {
+            *count += 1;
+            handle.clone()
+        }
+        None => {
+            let handle = world
+                .get_resource_mut::<Assets<ColorMaterial>>()
+                .unwrap()
+                .add(if let Some(image) = image {
+                    ColorMaterial::modulated_texture(image.clone(), color)
+                } else {
+                    color.into()
+                });
+            cache.materials.insert(key, (handle.clone(), 1));
+            cache.keys.insert(handle.id, key);
+            handle
+        }
+    };
+    world.insert_resource(cache);
+    handle
+}
+
+/// Switches `entity` to the material for `color` and `image`, if it isn't
+/// using it already.
+pub(crate) fn update_material(
+    world: &mut World,
+    entity: Entity,
+    color: Color,
+    image: &Option<Handle<Texture>>,
+) {
+    let current = world.get::<Handle<ColorMaterial>>(entity).unwrap().id;
+    let unchanged = world
+        .get_resource::<MaterialCache>()
+        .and_then(|cache| cache.keys.get(&current))
+        .map_or(false, |current| *current == key(color, image));
+    if !unchanged {
+        let handle = new_material(world, color, image);
+        release_material(world, entity);
+        *world.get_mut::<Handle<ColorMaterial>>(entity).unwrap() = handle;
+    }
+}
+
+/// Gives back the material of `entity`, before it's despawned or switches
+/// materials. Entities without a material from the cache are ignored.
+pub(crate) fn release_material(world: &mut World, entity: Entity) {
+    let id = match world.get::<Handle<ColorMaterial>>(entity) {
+        Some(handle) => handle.id,
+        None => return,
+    };
+    let mut cache = match world.get_resource_mut::<MaterialCache>() {
+        Some(cache) => cache,
+        None => return,
+    };
+    let key = match cache.keys.get(&id) {
+        Some(key) => *key,
+        None => return,
+    };
+    let (_, count) = cache.materials.get_mut(&key).unwrap();
+    *count -= 1;
+    if *count == 0 {
+        cache.materials.remove(&key);
+        cache.keys.remove(&id);
+    }
+}
@@ -0,0 +1,145 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use bevy::{
+    app::{Events, ManualEventReader},
+    math::Vec2,
+    prelude::*,
+};
+
+use crate::focus::{press_key, UiFocus};
+
+/// How far the left stick has to be pushed to move the focus. It has to come
+/// back under half of that before it moves the focus again.
+const STICK_THRESHOLD: f32 = 0.6;
+
+/// Moves the focus with gamepads, for menus that are played without a mouse.
+///
+/// The d-pad and the left stick move the focus to the nearest focusable unit
+/// in that direction, going by where the units were laid out. South confirms,
+/// as return would on a keyboard: it clicks a focused button, and sends a
+/// [`KeyEvent`](crate::KeyEvent) for `KeyCode::Return`. East goes back,
+/// sending one for `KeyCode::Escape`, which `on_key` handlers up the tree can
+/// use to close their menu.
+#[derive(Default)]
+pub(crate) struct GamepadNavigation {
+    events: ManualEventReader<GamepadEvent>,
+    sticks: HashMap<Gamepad, Vec2>,
+}
+
+pub(crate) fn dispatch_gamepad_events(world: &mut World) {
+    let mut nav = world
+        .remove_resource::<GamepadNavigation>()
+        .unwrap_or_default();
+    let mut moves = vec![];
+    if let Some(events) = world.get_resource::<Events<GamepadEvent>>() {
+        for GamepadEvent(gamepad, event) in nav.events.iter(events) {
+            let stick = nav.sticks.entry(*gamepad).or_default();
+            let old = *stick;
+            match event {
+                GamepadEventType::AxisChanged(GamepadAxisType::LeftStickX, x) => stick.x = *x,
+                GamepadEventType::AxisChanged(GamepadAxisType::LeftStickY, y) => stick.y = *y,
+                GamepadEventType::Disconnected => *stick = Vec2::ZERO,
+                _ => continue,
+            }
+            if let Some(direction) = stick_pushed(old, *stick) {
+                moves.push(direction);
+            }
+        }
+    }
+    world.insert_resource(nav);
+
+    let (mut confirm, mut back) = (false, false);
+    if let Some(input) = world.get_resource::<Input<GamepadButton>>() {
+        for GamepadButton(_, button) in input.get_just_pressed() {
+            match button {
+                GamepadButtonType::DPadUp => moves.push(Vec2::Y),
+                GamepadButtonType::DPadDown => moves.push(-Vec2::Y),
+                GamepadButtonType::DPadLeft => moves.push(-Vec2::X),
+                GamepadButtonType::DPadRight => moves.push(Vec2::X),
+                GamepadButtonType::South => confirm = true,
+                GamepadButtonType::East => back = true,
+                _ => {}
+            }
+        }
+    }
+
+    for direction in moves {
+        move_focus(world, direction);
+    }
+    if confirm {
+        press_key(world, KeyCode::Return, false, false, false);
+    }
+    if back {
+        press_key(world, KeyCode::Escape, false, false, false);
+    }
+}
+
+/// The direction the stick was just pushed in, if it crossed the threshold.
+fn stick_pushed(old: Vec2, new: Vec2) -> Option<Vec2> {
+    let pushed = |old: f32, new: f32| {
+        if new.abs() > STICK_THRESHOLD && old.abs() <= STICK_THRESHOLD / 2.0 {
+            Some(new.signum())
+        } else {
+            None
+        }
+    };
+    // One direction at a time, the one pushed furthest.
+    if new.x.abs() >= new.y.abs() {
+        pushed(old.x, new.x).map(|sign| Vec2::X * sign)
+    } else {
+        pushed(old.y, new.y).map(|sign| Vec2::Y * sign)
+    }
+}
+
+/// Focuses the focusable unit closest to the focused one in `direction`, or
+/// the first one if nothing has focus yet.
+fn move_focus(world: &mut World, direction: Vec2) {
+    let focus = match world.get_resource::<UiFocus>() {
+        Some(focus) => focus,
+        None => return,
+    };
+    let next = match focus.focused.and_then(|focused| rect(world, focused)) {
+        Some(from) => {
+            let focused = focus.focused;
+            focus
+                .entities
+                .values()
+                .copied()
+                .filter(|&e| Some(e) != focused)
+                .filter_map(|e| Some((e, distance(from, rect(world, e)?, direction)?)))
+                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                .map(|(e, _)| e)
+        }
+        None => focus
+            .order
+            .next(None)
+            .and_then(|id| focus.entities.get(&id).copied()),
+    };
+    if let Some(next) = next {
+        world.get_resource_mut::<UiFocus>().unwrap().focused = Some(next);
+    }
+}
+
+/// The laid out center and size of `entity`.
+fn rect(world: &World, entity: Entity) -> Option<(Vec2, Vec2)> {
+    let node = world.get::<Node>(entity)?;
+    let transform = world.get::<GlobalTransform>(entity)?;
+    Some((transform.translation.truncate(), node.size))
+}
+
+/// How far `to` is from `from` going in `direction`, or `None` if it isn't in
+/// that direction at all. Units off to the side count as further away than
+/// units straight ahead.
+fn distance(from: (Vec2, Vec2), to: (Vec2, Vec2), direction: Vec2) -> Option<f32> {
+    let ((from, from_size), (to, to_size)) = (from, to);
+    let delta = to - from;
+    let ahead = delta.dot(direction);
+    // Units overlapping for more than half their extent, such as a parent
+    // and its child, aren't in any direction of each other.
+    let extent = (from_size + to_size).dot(direction.abs()) / 2.0;
+    if ahead <= 0.0 || ahead < extent / 2.0 {
+        return None;
+    }
+    let aside = delta.dot(direction.perp()).abs();
+    Some(ahead + 2.0 * aside)
+}
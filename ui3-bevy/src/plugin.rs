@@ -0,0 +1,572 @@
+use bevy::{
+    app::{AppExit, Events},
+    prelude::*,
+};
+use std::collections::{BTreeMap, HashMap};
+use ui3_core::{FocusOrder, RenderNode, UnitId};
+
+use crate::{
+    events::dispatch_pointer_events,
+    focus::{dispatch_focus_events, UiFocus},
+    image::{release_image, update_images, ImageData},
+    material::{new_material, release_material, update_material},
+    navigation::dispatch_gamepad_events,
+    prelude::{Callback, SetState, UiApp, WidgetNode},
+    text_input::{dispatch_text_input, input_text, TextInputData},
+    BevyBackend, ButtonState, Handlers, UiLocals, Unit,
+};
+
+/// Runs a ui3 application rendering into Bevy UI.
+///
+/// ```ignore
+/// App::build()
+///     .add_plugins(DefaultPlugins)
+///     .add_plugin(Ui3Plugin::new(root).font("FiraMono-Medium.ttf"))
+///     .run();
+/// ```
+///
+/// The application is created at startup from `root` and lives in the
+/// [`UiApp`] non-send resource. It's updated and rendered at the end of every
+/// `Update` stage, so the layout is computed the same frame, and unmounted
+/// when the app exits. The values of local stores and the callbacks of units
+/// are kept in a non-send resource as well, so they're never dropped off the
+/// main thread.
+pub struct Ui3Plugin {
+    root: fn() -> WidgetNode,
+    font: Option<String>,
+    spawn_camera: bool,
+}
+
+impl Ui3Plugin {
+    pub fn new(root: fn() -> WidgetNode) -> Self {
+        Self {
+            root,
+            font: None,
+            spawn_camera: true,
+        }
+    }
+
+    /// Loads the font at `path` into the [`UiFont`] resource.
+    pub fn font(mut self, path: impl Into<String>) -> Self {
+        self.font = Some(path.into());
+        self
+    }
+
+    /// Leaves spawning the UI camera to the game.
+    pub fn without_camera(mut self) -> Self {
+        self.spawn_camera = false;
+        self
+    }
+}
+
+impl Plugin for Ui3Plugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(UiSettings {
+            root: self.root,
+            font: self.font.clone(),
+            spawn_camera: self.spawn_camera,
+        })
+        .add_startup_system(ui_startup_system.exclusive_system())
+        .add_system(ui_system.exclusive_system().at_end())
+        .add_system(ui_teardown_system.exclusive_system().at_end());
+    }
+}
+
+/// The font passed to [`Ui3Plugin::font`], for widgets to read through
+/// [`UiRes`](crate::UiRes).
+pub struct UiFont(pub Handle<Font>);
+
+struct UiSettings {
+    root: fn() -> WidgetNode,
+    font: Option<String>,
+    spawn_camera: bool,
+}
+
+/// The entities rendered for the mounted units, kept across frames so Bevy
+/// side state such as `Interaction` survives re-renders.
+#[derive(Default)]
+struct UiEntities {
+    units: HashMap<UnitId, Entity>,
+    root: Option<Entity>,
+    portals: BTreeMap<String, Entity>,
+}
+
+/// Which kind of unit an entity was spawned for, so it can be respawned when
+/// a unit changes kind.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum UnitKind {
+    Node,
+    Text,
+    Button,
+    TextInput,
+    Image,
+}
+
+impl UnitKind {
+    fn of(unit: &Unit) -> Self {
+        match unit {
+            Unit::Node { .. } => UnitKind::Node,
+            Unit::Text { .. } => UnitKind::Text,
+            Unit::Button { .. } => UnitKind::Button,
+            Unit::TextInput { .. } => UnitKind::TextInput,
+            Unit::Image { .. } => UnitKind::Image,
+        }
+    }
+}
+
+pub(crate) struct ButtonData {
+    /// As of the last dispatch.
+    interaction: Interaction,
+}
+
+/// The callbacks of a button, kept in [`UiLocals`].
+pub(crate) struct ButtonCallbacks {
+    pub(crate) func: Callback,
+    state: Option<SetState<ButtonState>>,
+}
+
+fn ui_startup_system(world: &mut World) {
+    let settings = world.remove_resource::<UiSettings>().unwrap();
+    // Inserted before the application is mounted, so widgets can read it
+    // from their first render on.
+    if let Some(font) = settings.font {
+        let font = world
+            .get_resource::<AssetServer>()
+            .unwrap()
+            .load(font.as_str());
+        world.insert_resource(UiFont(font));
+    }
+    if settings.spawn_camera {
+        world.spawn().insert_bundle(UiCameraBundle::default());
+    }
+    let app = UiApp::new((settings.root)(), world);
+    world.insert_non_send(app);
+}
+
+fn ui_system(world: &mut World) {
+    let mut app = match world.remove_non_send::<UiApp>() {
+        Some(app) => app,
+        None => return,
+    };
+    dispatch_button_events(world);
+    dispatch_pointer_events(world);
+    dispatch_focus_events(world);
+    dispatch_gamepad_events(world);
+    dispatch_text_input(world);
+    app.update(world);
+
+    let mut entities = world.remove_resource::<UiEntities>().unwrap_or_default();
+    let mut rendered = HashMap::new();
+    let roots = app.render();
+    let focus_order = FocusOrder::new(&roots);
+
+    let root = *entities
+        .root
+        .get_or_insert_with(|| world.spawn().insert_bundle(NodeBundle::default()).id());
+    let children: Vec<_> = roots
+        .main
+        .iter()
+        .map(|n| sync_node(n, world, &mut entities, &mut rendered))
+        .collect();
+    set_children(world, root, &children);
+
+    // Portal roots are spawned after the main root so they're drawn on top of it.
+    let mut portals = BTreeMap::new();
+    for (target, nodes) in roots.portals {
+        let portal = entities.portals.remove(target).unwrap_or_else(|| {
+            world
+                .spawn()
+                .insert_bundle(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .id()
+        });
+        let children: Vec<_> = nodes
+            .iter()
+            .map(|n| sync_node(n, world, &mut entities, &mut rendered))
+            .collect();
+        set_children(world, portal, &children);
+        portals.insert(target.to_string(), portal);
+    }
+
+    // Whatever wasn't rendered this time belongs to units that were unmounted.
+    entities
+        .units
+        .drain()
+        .map(|(_, entity)| entity)
+        .chain(std::mem::take(&mut entities.portals).into_values())
+        .for_each(|entity| despawn_unit(world, entity));
+    update_focus(world, focus_order, &rendered);
+    update_images(world);
+    entities.units = rendered;
+    entities.portals = portals;
+    world.insert_resource(entities);
+    world.insert_non_send(app);
+}
+
+/// Keeps the focus order in sync with the render, and drops the focus if its
+/// unit went away.
+fn update_focus(world: &mut World, order: FocusOrder, rendered: &HashMap<UnitId, Entity>) {
+    let mut focus = world.get_resource_or_insert_with(UiFocus::default);
+    focus.entities = order.iter().map(|id| (id, rendered[&id])).collect();
+    if let Some(focused) = focus.focused {
+        if !focus.entities.values().any(|&e| e == focused) {
+            focus.focused = None;
+        }
+    }
+    focus.order = order;
+}
+
+/// Updates the entity of `node` and its children to match it, spawning the
+/// ones that are missing.
+fn sync_node(
+    node: &RenderNode<BevyBackend>,
+    world: &mut World,
+    entities: &mut UiEntities,
+    rendered: &mut HashMap<UnitId, Entity>,
+) -> Entity {
+    let kind = UnitKind::of(node.unit);
+    let id = match entities.units.remove(&node.id) {
+        Some(id) if world.get::<UnitKind>(id) == Some(&kind) => {
+            update_unit(node.unit, world, id);
+            id
+        }
+        old => {
+            if let Some(old) = old {
+                despawn_unit(world, old);
+            }
+            spawn_unit(node.unit, world)
+        }
+    };
+    rendered.insert(node.id, id);
+    if let Some(node_ref) = node.node_ref {
+        node_ref.set(Some(id));
+    }
+    // The parts of nine-slice images go below the unit's children.
+    let mut children = world
+        .get::<ImageData>(id)
+        .map_or(vec![], |image| image.parts.clone());
+    children.extend(
+        node.children
+            .iter()
+            .map(|n| sync_node(n, world, entities, rendered)),
+    );
+    set_children(world, id, &children);
+    id
+}
+
+/// Despawns the entity of a unit, along with the entities it's made of.
+/// Children that are units are despawned on their own.
+fn despawn_unit(world: &mut World, entity: Entity) {
+    if let Some(mut locals) = world.get_non_send_resource_mut::<UiLocals>() {
+        locals.remove_unit(entity);
+    }
+    release_image(world, entity);
+    release_material(world, entity);
+    world.despawn(entity);
+}
+
+fn spawn_unit(unit: &Unit, world: &mut World) -> Entity {
+    let entity = match unit {
+        Unit::Node {
+            style,
+            color,
+            image,
+            handlers,
+        } => {
+            let material = new_material(world, *color, image);
+            let entity = world
+                .spawn()
+                .insert_bundle(NodeBundle {
+                    style: style.clone(),
+                    material,
+                    ..Default::default()
+                })
+                .id();
+            let mut locals = UiLocals::get_or_insert(world);
+            locals.handlers.insert(entity, handlers.clone());
+            entity
+        }
+        Unit::Text {
+            style,
+            text,
+            handlers,
+        } => {
+            let entity = world
+                .spawn()
+                .insert_bundle(TextBundle {
+                    style: style.clone(),
+                    text: text.clone(),
+                    ..Default::default()
+                })
+                .id();
+            let mut locals = UiLocals::get_or_insert(world);
+            locals.handlers.insert(entity, handlers.clone());
+            entity
+        }
+        Unit::Button {
+            func,
+            state,
+            style,
+            color,
+            image,
+        } => {
+            let material = new_material(world, *color, image);
+            let entity = world
+                .spawn()
+                .insert(ButtonData {
+                    interaction: Interaction::None,
+                })
+                .insert_bundle(ButtonBundle {
+                    style: style.clone(),
+                    material,
+                    ..Default::default()
+                })
+                .id();
+            let callbacks = ButtonCallbacks {
+                func: func.clone(),
+                state: *state,
+            };
+            UiLocals::get_or_insert(world)
+                .buttons
+                .insert(entity, callbacks);
+            entity
+        }
+        Unit::TextInput {
+            value,
+            placeholder,
+            style,
+            text_style,
+            on_change,
+        } => {
+            let entity = world
+                .spawn()
+                .insert_bundle(TextBundle {
+                    style: style.clone(),
+                    text: input_text(value, placeholder, text_style, false),
+                    ..Default::default()
+                })
+                .insert(TextInputData {
+                    value: value.clone(),
+                })
+                .id();
+            let mut locals = UiLocals::get_or_insert(world);
+            locals.text_inputs.insert(entity, on_change.clone());
+            entity
+        }
+        Unit::Image {
+            style,
+            image,
+            color,
+            mode,
+            rect,
+            slice,
+        } => {
+            // Transparent until the texture is loaded.
+            let material = new_material(world, Color::NONE, &None);
+            world
+                .spawn()
+                .insert_bundle(NodeBundle {
+                    style: style.clone(),
+                    material,
+                    ..Default::default()
+                })
+                .insert(ImageData::new(image, *color, *mode, rect, slice))
+                .id()
+        }
+    };
+    world.entity_mut(entity).insert(UnitKind::of(unit));
+    entity
+}
+
+/// Writes the parts of `unit` that changed into the components of `entity`.
+/// Untouched components aren't marked as changed, so Bevy doesn't redo their
+/// layout or meshes.
+fn update_unit(unit: &Unit, world: &mut World, entity: Entity) {
+    match unit {
+        Unit::Node {
+            style,
+            color,
+            image,
+            handlers,
+        } => {
+            update_style(world, entity, style);
+            update_material(world, entity, *color, image);
+            update_handlers(world, entity, handlers);
+        }
+        Unit::Text {
+            style,
+            text,
+            handlers,
+        } => {
+            update_style(world, entity, style);
+            let mut current = world.get_mut::<Text>(entity).unwrap();
+            if !text_eq(&current, text) {
+                *current = text.clone();
+            }
+            update_handlers(world, entity, handlers);
+        }
+        Unit::Button {
+            func,
+            state,
+            style,
+            color,
+            image,
+        } => {
+            update_style(world, entity, style);
+            update_material(world, entity, *color, image);
+            let interaction = world.get::<ButtonData>(entity).unwrap().interaction;
+            let mut locals = UiLocals::get_or_insert(world);
+            let current = locals.buttons.get_mut(&entity).unwrap();
+            if current.func != *func {
+                current.func = func.clone();
+            }
+            if current.state != *state {
+                current.state = *state;
+                if let Some(state) = state {
+                    state.set(world, interaction.into());
+                }
+            }
+        }
+        Unit::TextInput {
+            value,
+            placeholder,
+            style,
+            text_style,
+            on_change,
+        } => {
+            update_style(world, entity, style);
+            let mut current = world.get_mut::<TextInputData>(entity).unwrap();
+            if current.value != *value {
+                current.value = value.clone();
+            }
+            let mut locals = UiLocals::get_or_insert(world);
+            let current = locals.text_inputs.get_mut(&entity).unwrap();
+            if *current != *on_change {
+                *current = on_change.clone();
+            }
+            let focused = world
+                .get_resource::<UiFocus>()
+                .map_or(false, |focus| focus.focused == Some(entity));
+            let text = input_text(value, placeholder, text_style, focused);
+            let mut current = world.get_mut::<Text>(entity).unwrap();
+            if !text_eq(&current, &text) {
+                *current = text;
+            }
+        }
+        Unit::Image {
+            style,
+            image,
+            color,
+            mode,
+            rect,
+            slice,
+        } => {
+            let mut data = world.get_mut::<ImageData>(entity).unwrap();
+            data.update(image, *color, *mode, rect, slice);
+            let style = data.style(style);
+            update_style(world, entity, &style);
+        }
+    }
+}
+
+/// Calls the callbacks of the buttons that were clicked since the last frame,
+/// and passes on their new state.
+fn dispatch_button_events(world: &mut World) {
+    let mut changed = vec![];
+    let mut query = world.query::<(Entity, &Interaction, &mut ButtonData)>();
+    for (entity, interaction, mut data) in query.iter_mut(world) {
+        if *interaction == data.interaction {
+            continue;
+        }
+        // Releasing the button outside of it goes straight to `None`.
+        let clicked =
+            data.interaction == Interaction::Clicked && *interaction == Interaction::Hovered;
+        changed.push((entity, *interaction, clicked));
+        data.interaction = *interaction;
+    }
+    let mut clicked = vec![];
+    let mut states = vec![];
+    if let Some(locals) = world.get_non_send_resource::<UiLocals>() {
+        for (entity, interaction, was_clicked) in changed {
+            let callbacks = &locals.buttons[&entity];
+            if was_clicked {
+                clicked.push(callbacks.func.clone());
+            }
+            if let Some(state) = callbacks.state {
+                states.push((state, interaction));
+            }
+        }
+    }
+    for (state, interaction) in states {
+        state.set(world, interaction.into());
+    }
+    clicked.into_iter().for_each(|func| func.call(world, ()));
+}
+
+fn update_style(world: &mut World, entity: Entity, style: &Style) {
+    let mut current = world.get_mut::<Style>(entity).unwrap();
+    if *current != *style {
+        *current = style.clone();
+    }
+}
+
+fn update_handlers(world: &mut World, entity: Entity, handlers: &Handlers) {
+    let mut locals = UiLocals::get_or_insert(world);
+    let current = locals.handlers.get_mut(&entity).unwrap();
+    if *current != *handlers {
+        *current = handlers.clone();
+    }
+}
+
+// `Text` isn't `PartialEq`.
+fn text_eq(a: &Text, b: &Text) -> bool {
+    a.alignment.vertical == b.alignment.vertical
+        && a.alignment.horizontal == b.alignment.horizontal
+        && a.sections.len() == b.sections.len()
+        && a.sections.iter().zip(&b.sections).all(|(a, b)| {
+            a.value == b.value
+                && a.style.font == b.style.font
+                && a.style.font_size == b.style.font_size
+                && a.style.color == b.style.color
+        })
+}
+
+/// Makes `children` the children of `parent`, in that order.
+pub(crate) fn set_children(world: &mut World, parent: Entity, children: &[Entity]) {
+    let moved: Vec<_> = children
+        .iter()
+        .copied()
+        .filter(|&child| world.get::<Parent>(child).map(|p| p.0) != Some(parent))
+        .collect();
+    if !moved.is_empty() {
+        // Leaves the children listed in their previous parent's `Children`,
+        // which is rewritten when that parent is synced or despawned.
+        world.entity_mut(parent).push_children(&moved);
+    }
+    let current = world.get::<Children>(parent).map(|c| &**c);
+    if current.unwrap_or(&[]) != children {
+        world.entity_mut(parent).insert(Children::with(children));
+    }
+}
+
+fn ui_teardown_system(world: &mut World) {
+    let exiting = world
+        .get_resource::<Events<AppExit>>()
+        .map_or(false, |events| {
+            events.iter_current_update_events().next().is_some()
+        });
+    if exiting {
+        if let Some(app) = world.remove_non_send::<UiApp>() {
+            app.unmount(world);
+        }
+        // Unmounting released every local store, this drops the values of any
+        // that were leaked, and the callbacks of the units, while still on the
+        // main thread.
+        world.remove_non_send::<UiLocals>();
+    }
+}
@@ -0,0 +1,472 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+/// Builds a [`Text`] out of spans with different styles, for
+/// [`Unit::text`](crate::Unit::text).
+///
+/// ```ignore
+/// let text = RichText::new(TextStyle { font, font_size: 20.0, color: Color::WHITE })
+///     .bold_font(bold)
+///     .markup(&tr("press-start"))?
+///     .build();
+/// ```
+///
+/// Spans are added either one by one with [`span`](Self::span), or from
+/// markup where `tr("press-start")` could be
+/// `"Press <b>Start</b> to <color=#ffd700>begin</color>"`. The markup
+/// supports:
+///
+/// - `<b>...</b>` and `<i>...</i>`, using the fonts passed to
+///   [`bold_font`](Self::bold_font), [`italic_font`](Self::italic_font) and
+///   [`bold_italic_font`](Self::bold_italic_font), or the base font if they
+///   weren't.
+/// - `<color=#rrggbb>...</color>`, also with 3, 4 or 8 hex digits.
+/// - `<size=24>...</size>`, in the same unit as `TextStyle::font_size`, and
+///   greater than 0.
+/// - `<<` for a literal `<`.
+///
+/// Tags nest, and have to be closed in the reverse order they were opened.
+#[derive(Clone)]
+pub struct RichText {
+    style: TextStyle,
+    bold: Option<Handle<Font>>,
+    italic: Option<Handle<Font>>,
+    bold_italic: Option<Handle<Font>>,
+    sections: Vec<TextSection>,
+    alignment: TextAlignment,
+}
+
+impl RichText {
+    /// `style` applies to the text outside of any tag.
+    pub fn new(style: TextStyle) -> Self {
+        Self {
+            style,
+            bold: None,
+            italic: None,
+            bold_italic: None,
+            sections: vec![],
+            alignment: Default::default(),
+        }
+    }
+
+    pub fn bold_font(mut self, font: Handle<Font>) -> Self {
+        self.bold = Some(font);
+        self
+    }
+
+    pub fn italic_font(mut self, font: Handle<Font>) -> Self {
+        self.italic = Some(font);
+        self
+    }
+
+    /// The font for text both bold and italic. Falls back to the bold font,
+    /// then the italic one.
+    pub fn bold_italic_font(mut self, font: Handle<Font>) -> Self {
+        self.bold_italic = Some(font);
+        self
+    }
+
+    pub fn alignment(mut self, alignment: TextAlignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Adds `text` with the base style.
+    pub fn text(self, text: impl Into<String>) -> Self {
+        let style = self.style.clone();
+        self.span(text, style)
+    }
+
+    pub fn span(mut self, text: impl Into<String>, style: TextStyle) -> Self {
+        let value = text.into();
+        if !value.is_empty() {
+            self.sections.push(TextSection { value, style });
+        }
+        self
+    }
+
+    /// Adds the spans described by `markup`.
+    pub fn markup(mut self, markup: &str) -> Result<Self, MarkupError> {
+        // The open tags, and the style they apply.
+        let mut open: Vec<(&str, SpanStyle)> = vec![];
+        let mut style = SpanStyle {
+            bold: false,
+            italic: false,
+            color: self.style.color,
+            size: self.style.font_size,
+        };
+        let mut text = String::new();
+        let mut rest = markup;
+        while let Some(start) = rest.find('<') {
+            text.push_str(&rest[..start]);
+            rest = &rest[start + 1..];
+            if let Some(after) = rest.strip_prefix('<') {
+                text.push('<');
+                rest = after;
+                continue;
+            }
+            let end = rest.find('>').ok_or_else(|| MarkupError::UnterminatedTag {
+                tag: rest.to_string(),
+            })?;
+            let tag = &rest[..end];
+            rest = &rest[end + 1..];
+
+            let span_style = self.section_style(&style);
+            self = self.span(std::mem::take(&mut text), span_style);
+            if let Some(name) = tag.strip_prefix('/') {
+                match open.pop() {
+                    Some((opened, previous)) if opened == name => style = previous,
+                    opened => {
+                        return Err(MarkupError::Mismatched {
+                            expected: opened.map(|(opened, _)| opened.to_string()),
+                            found: name.to_string(),
+                        })
+                    }
+                }
+                continue;
+            }
+            let (name, value) = match tag.find('=') {
+                Some(eq) => (&tag[..eq], Some(&tag[eq + 1..])),
+                None => (tag, None),
+            };
+            let invalid = || MarkupError::InvalidValue {
+                tag: name.to_string(),
+                value: value.unwrap_or_default().to_string(),
+            };
+            open.push((name, style));
+            match (name, value) {
+                ("b", None) => style.bold = true,
+                ("i", None) => style.italic = true,
+                ("color", Some(value)) => {
+                    let hex = value.strip_prefix('#').unwrap_or(value);
+                    style.color = Color::hex(hex).map_err(|_| invalid())?;
+                }
+                ("size", Some(value)) => {
+                    style.size = value
+                        .parse()
+                        .ok()
+                        .filter(|size: &f32| size.is_finite() && *size > 0.0)
+                        .ok_or_else(invalid)?;
+                }
+                ("b", Some(_)) | ("i", Some(_)) | ("color", None) | ("size", None) => {
+                    return Err(invalid())
+                }
+                _ => {
+                    return Err(MarkupError::UnknownTag {
+                        tag: name.to_string(),
+                    })
+                }
+            }
+        }
+        text.push_str(rest);
+        if let Some((tag, _)) = open.pop() {
+            return Err(MarkupError::Unclosed {
+                tag: tag.to_string(),
+            });
+        }
+        let span_style = self.section_style(&style);
+        Ok(self.span(text, span_style))
+    }
+
+    pub fn build(self) -> Text {
+        Text {
+            sections: self.sections,
+            alignment: self.alignment,
+        }
+    }
+
+    fn section_style(&self, style: &SpanStyle) -> TextStyle {
+        let font = match (style.bold, style.italic) {
+            (true, true) => self
+                .bold_italic
+                .as_ref()
+                .or(self.bold.as_ref())
+                .or(self.italic.as_ref()),
+            (true, false) => self.bold.as_ref(),
+            (false, true) => self.italic.as_ref(),
+            (false, false) => None,
+        };
+        TextStyle {
+            font: font.unwrap_or(&self.style.font).clone(),
+            font_size: style.size,
+            color: style.color,
+        }
+    }
+}
+
+impl From<RichText> for Text {
+    fn from(text: RichText) -> Self {
+        text.build()
+    }
+}
+
+/// The style of markup text, as set by the tags around it.
+#[derive(Clone, Copy)]
+struct SpanStyle {
+    bold: bool,
+    italic: bool,
+    color: Color,
+    size: f32,
+}
+
+/// Returned by [`RichText::markup`] for malformed markup.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarkupError {
+    /// A `<` without a `>` after it.
+    UnterminatedTag {
+        tag: String,
+    },
+    UnknownTag {
+        tag: String,
+    },
+    /// A tag given a value it doesn't take, or missing one it needs.
+    InvalidValue {
+        tag: String,
+        value: String,
+    },
+    /// A closing tag for another tag than the last one opened.
+    Mismatched {
+        expected: Option<String>,
+        found: String,
+    },
+    /// A tag still open at the end of the markup.
+    Unclosed {
+        tag: String,
+    },
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkupError::UnterminatedTag { tag } => write!(f, "tag `<{}` is missing its `>`", tag),
+            MarkupError::UnknownTag { tag } => write!(f, "unknown tag `{}`", tag),
+            MarkupError::InvalidValue { tag, value } => {
+                write!(f, "invalid value `{}` for tag `{}`", value, tag)
+            }
+            MarkupError::Mismatched {
+                expected: Some(expected),
+                found,
+            } => write!(f, "expected `</{}>`, found `</{}>`", expected, found),
+            MarkupError::Mismatched {
+                expected: None,
+                found,
+            } => write!(f, "`</{}>` closes a tag that wasn't opened", found),
+            MarkupError::Unclosed { tag } => write!(f, "tag `{}` is never closed", tag),
+        }
+    }
+}
+
+impl std::error::Error for MarkupError {}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{asset::HandleId, prelude::*};
+
+    use super::{MarkupError, RichText};
+
+    struct Fonts {
+        base: Handle<Font>,
+        bold: Handle<Font>,
+        italic: Handle<Font>,
+        bold_italic: Handle<Font>,
+    }
+
+    impl Fonts {
+        fn new() -> Self {
+            let font = || Handle::weak(HandleId::random::<Font>());
+            Self {
+                base: font(),
+                bold: font(),
+                italic: font(),
+                bold_italic: font(),
+            }
+        }
+
+        fn text(&self) -> RichText {
+            RichText::new(TextStyle {
+                font: self.base.clone(),
+                font_size: 20.0,
+                color: Color::WHITE,
+            })
+            .bold_font(self.bold.clone())
+            .italic_font(self.italic.clone())
+            .bold_italic_font(self.bold_italic.clone())
+        }
+    }
+
+    fn parse(markup: &str) -> Result<Vec<TextSection>, MarkupError> {
+        Fonts::new()
+            .text()
+            .markup(markup)
+            .map(|text| text.build().sections)
+    }
+
+    fn values(sections: &[TextSection]) -> Vec<&str> {
+        sections
+            .iter()
+            .map(|section| section.value.as_str())
+            .collect()
+    }
+
+    fn error(markup: &str) -> MarkupError {
+        parse(markup).unwrap_err()
+    }
+
+    fn invalid(tag: &str, value: &str) -> MarkupError {
+        MarkupError::InvalidValue {
+            tag: tag.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn nested_bold_italic() {
+        let fonts = Fonts::new();
+        let sections = fonts
+            .text()
+            .markup("a<b>b<i>c</i>d</b><i>e</i>f")
+            .unwrap()
+            .build()
+            .sections;
+        assert_eq!(values(&sections), ["a", "b", "c", "d", "e", "f"]);
+        let used: Vec<_> = sections.iter().map(|section| &section.style.font).collect();
+        assert_eq!(
+            used,
+            [
+                &fonts.base,
+                &fonts.bold,
+                &fonts.bold_italic,
+                &fonts.bold,
+                &fonts.italic,
+                &fonts.base
+            ]
+        );
+
+        // Without a bold italic font, the bold one is used.
+        let sections = RichText::new(TextStyle {
+            font: fonts.base.clone(),
+            ..Default::default()
+        })
+        .bold_font(fonts.bold.clone())
+        .italic_font(fonts.italic.clone())
+        .markup("<i><b>x</b></i>")
+        .unwrap()
+        .build()
+        .sections;
+        assert_eq!(sections[0].style.font, fonts.bold);
+    }
+
+    #[test]
+    fn color_and_size() {
+        let sections = parse("<color=#f00>r<size=32>R</size></color><size=8.5>s</size>").unwrap();
+        assert_eq!(values(&sections), ["r", "R", "s"]);
+        let red = Color::hex("f00").unwrap();
+        assert_eq!(sections[0].style.color, red);
+        assert_eq!(sections[0].style.font_size, 20.0);
+        assert_eq!(sections[1].style.color, red);
+        assert_eq!(sections[1].style.font_size, 32.0);
+        assert_eq!(sections[2].style.color, Color::WHITE);
+        assert_eq!(sections[2].style.font_size, 8.5);
+
+        let sections = parse("<color=80ff0080>a</color>").unwrap();
+        assert_eq!(sections[0].style.color, Color::hex("80ff0080").unwrap());
+    }
+
+    #[test]
+    fn escaped_less_than() {
+        let sections = parse("1 << 2, <<b> and <b><<</b>").unwrap();
+        assert_eq!(values(&sections), ["1 < 2, <b> and ", "<"]);
+        assert_eq!(sections[0].style.font_size, 20.0);
+
+        assert_eq!(values(&parse("<<<<").unwrap()), ["<<"]);
+        assert_eq!(values(&parse("a > b").unwrap()), ["a > b"]);
+    }
+
+    #[test]
+    fn mismatched_tags() {
+        assert_eq!(
+            error("<b><i>x</b></i>"),
+            MarkupError::Mismatched {
+                expected: Some("i".to_string()),
+                found: "b".to_string(),
+            }
+        );
+        assert_eq!(
+            error("x</b>"),
+            MarkupError::Mismatched {
+                expected: None,
+                found: "b".to_string(),
+            }
+        );
+        assert_eq!(
+            error("<color=#fff>x</color=#fff>"),
+            MarkupError::Mismatched {
+                expected: Some("color".to_string()),
+                found: "color=#fff".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn unclosed_tags() {
+        assert_eq!(
+            error("<b>x"),
+            MarkupError::Unclosed {
+                tag: "b".to_string()
+            }
+        );
+        assert_eq!(
+            error("<b><i>x</i>"),
+            MarkupError::Unclosed {
+                tag: "b".to_string()
+            }
+        );
+        assert_eq!(
+            error("x <b"),
+            MarkupError::UnterminatedTag {
+                tag: "b".to_string()
+            }
+        );
+        assert_eq!(
+            error("x <"),
+            MarkupError::UnterminatedTag { tag: String::new() }
+        );
+    }
+
+    #[test]
+    fn unknown_tags() {
+        assert_eq!(
+            error("<u>x</u>"),
+            MarkupError::UnknownTag {
+                tag: "u".to_string()
+            }
+        );
+        assert_eq!(
+            error("<b>x<font=mono>y</font></b>"),
+            MarkupError::UnknownTag {
+                tag: "font".to_string()
+            }
+        );
+        assert_eq!(error("<>"), MarkupError::UnknownTag { tag: String::new() });
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert_eq!(error("<color=#ggg>x</color>"), invalid("color", "#ggg"));
+        assert_eq!(error("<color=#12345>x</color>"), invalid("color", "#12345"));
+        assert_eq!(error("<color=>x</color>"), invalid("color", ""));
+        assert_eq!(error("<color=é>x</color>"), invalid("color", "é"));
+        assert_eq!(error("<color>x</color>"), invalid("color", ""));
+
+        assert_eq!(error("<size=big>x</size>"), invalid("size", "big"));
+        assert_eq!(error("<size=>x</size>"), invalid("size", ""));
+        assert_eq!(error("<size=0>x</size>"), invalid("size", "0"));
+        assert_eq!(error("<size=-4>x</size>"), invalid("size", "-4"));
+        assert_eq!(error("<size=NaN>x</size>"), invalid("size", "NaN"));
+        assert_eq!(error("<size=inf>x</size>"), invalid("size", "inf"));
+        assert_eq!(error("<size>x</size>"), invalid("size", ""));
+
+        assert_eq!(error("<b=1>x</b>"), invalid("b", "1"));
+    }
+}
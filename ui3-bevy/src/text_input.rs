@@ -0,0 +1,371 @@
+use std::ops::Range;
+
+use bevy::{
+    app::{Events, ManualEventReader},
+    input::{keyboard::KeyboardInput, ElementState, Input},
+    prelude::*,
+    window::ReceivedCharacter,
+};
+
+use crate::{focus::UiFocus, UiLocals};
+
+/// The contents of a [`Unit::TextInput`](crate::Unit::TextInput), along with
+/// where the caret and selection are. Positions count chars, not bytes, and
+/// positions past the end of the text count as the end.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextInputValue {
+    pub text: String,
+    pub caret: usize,
+    /// Where the selection started, it ends at the caret.
+    pub anchor: Option<usize>,
+}
+
+impl TextInputValue {
+    /// `text`, with the caret at its end.
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        Self {
+            caret: text.chars().count(),
+            text,
+            anchor: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// The selected chars, if any.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let len = self.len();
+        let (anchor, caret) = (self.anchor?.min(len), self.caret.min(len));
+        let range = anchor.min(caret)..anchor.max(caret);
+        if range.is_empty() {
+            None
+        } else {
+            Some(range)
+        }
+    }
+
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(char_index)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    /// Brings the caret and anchor back within the text.
+    fn clamp(&mut self) {
+        let len = self.len();
+        self.caret = self.caret.min(len);
+        if let Some(anchor) = &mut self.anchor {
+            *anchor = (*anchor).min(len);
+        }
+    }
+
+    /// Removes the selection, returning whether there was one.
+    fn delete_selection(&mut self) -> bool {
+        self.clamp();
+        match self.selection() {
+            Some(range) => {
+                let bytes = self.byte_index(range.start)..self.byte_index(range.end);
+                self.text.replace_range(bytes, "");
+                self.caret = range.start;
+                self.anchor = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replaces the selection with `s`, or inserts it at the caret.
+    pub fn insert(&mut self, s: &str) {
+        self.delete_selection();
+        let at = self.byte_index(self.caret);
+        self.text.insert_str(at, s);
+        self.caret += s.chars().count();
+    }
+
+    pub fn backspace(&mut self) {
+        if !self.delete_selection() && self.caret > 0 {
+            let at = self.byte_index(self.caret - 1);
+            self.text.remove(at);
+            self.caret -= 1;
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if !self.delete_selection() && self.caret < self.len() {
+            let at = self.byte_index(self.caret);
+            self.text.remove(at);
+        }
+    }
+
+    /// Moves the caret to `caret`, extending the selection if `select` is set
+    /// and dropping it otherwise.
+    pub fn move_to(&mut self, caret: usize, select: bool) {
+        self.clamp();
+        if select {
+            self.anchor.get_or_insert(self.caret);
+        } else {
+            self.anchor = None;
+        }
+        self.caret = caret.min(self.len());
+    }
+
+    pub fn move_left(&mut self, select: bool) {
+        self.clamp();
+        match self.selection() {
+            Some(range) if !select => self.move_to(range.start, false),
+            _ => self.move_to(self.caret.saturating_sub(1), select),
+        }
+    }
+
+    pub fn move_right(&mut self, select: bool) {
+        self.clamp();
+        match self.selection() {
+            Some(range) if !select => self.move_to(range.end, false),
+            _ => self.move_to(self.caret + 1, select),
+        }
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.caret = self.len();
+    }
+}
+
+/// The value a text input last reported, its `on_change` is kept in
+/// [`UiLocals`].
+pub(crate) struct TextInputData {
+    pub(crate) value: TextInputValue,
+}
+
+#[derive(Default)]
+struct TextInputReaders {
+    chars: ManualEventReader<ReceivedCharacter>,
+    keys: ManualEventReader<KeyboardInput>,
+}
+
+const SELECTION_COLOR: Color = Color::rgb(0.25, 0.45, 0.95);
+
+/// Builds what a text input shows: its value with the caret and selection,
+/// or its placeholder, dimmed.
+pub(crate) fn input_text(
+    value: &TextInputValue,
+    placeholder: &str,
+    style: &TextStyle,
+    focused: bool,
+) -> Text {
+    let section = |value: &str, color: Color| TextSection {
+        value: value.to_string(),
+        style: TextStyle {
+            color,
+            ..style.clone()
+        },
+    };
+    if value.is_empty() && !focused {
+        let mut dimmed = style.color;
+        dimmed.set_a(dimmed.a() * 0.5);
+        return Text {
+            sections: vec![section(placeholder, dimmed)],
+            alignment: Default::default(),
+        };
+    }
+    let (start, end) = value
+        .selection()
+        .map_or((value.caret, value.caret), |r| (r.start, r.end));
+    let (start, end) = (value.byte_index(start), value.byte_index(end));
+    let caret = if focused { "|" } else { "" };
+    let sections = if value.selection().is_none() {
+        vec![
+            section(&value.text[..start], style.color),
+            section(caret, style.color),
+            section(&value.text[start..], style.color),
+        ]
+    } else {
+        vec![
+            section(&value.text[..start], style.color),
+            section(&value.text[start..end], SELECTION_COLOR),
+            section(&value.text[end..], style.color),
+        ]
+    };
+    Text {
+        sections,
+        alignment: Default::default(),
+    }
+}
+
+/// Applies the typing since the last frame to the focused text input, and
+/// reports the new value through its `on_change`.
+pub(crate) fn dispatch_text_input(world: &mut World) {
+    let mut readers = world
+        .remove_resource::<TextInputReaders>()
+        .unwrap_or_default();
+
+    // Read even without a focused input, so old typing doesn't show up once
+    // one is focused.
+    let keys: Vec<_> = world
+        .get_resource::<Events<KeyboardInput>>()
+        .map(|events| {
+            readers
+                .keys
+                .iter(events)
+                .filter(|key| key.state == ElementState::Pressed)
+                .filter_map(|key| key.key_code)
+                .collect()
+        })
+        .unwrap_or_default();
+    let chars: String = world
+        .get_resource::<Events<ReceivedCharacter>>()
+        .map(|events| {
+            readers
+                .chars
+                .iter(events)
+                .map(|c| c.char)
+                .filter(|c| !c.is_control())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    world.insert_resource(readers);
+    let focused = world
+        .get_resource::<UiFocus>()
+        .and_then(|focus| focus.focused);
+    let entity = match focused {
+        Some(entity) => entity,
+        None => return,
+    };
+    let (shift, control) = world
+        .get_resource::<Input<KeyCode>>()
+        .map_or((false, false), |input| {
+            (
+                input.pressed(KeyCode::LShift) || input.pressed(KeyCode::RShift),
+                input.pressed(KeyCode::LControl) || input.pressed(KeyCode::RControl),
+            )
+        });
+    let mut data = match world.get_mut::<TextInputData>(entity) {
+        Some(data) => data,
+        None => return,
+    };
+    let mut value = data.value.clone();
+    for key in keys {
+        match key {
+            KeyCode::Back => value.backspace(),
+            KeyCode::Delete => value.delete(),
+            KeyCode::Left => value.move_left(shift),
+            KeyCode::Right => value.move_right(shift),
+            KeyCode::Home => value.move_to(0, shift),
+            KeyCode::End => value.move_to(value.len(), shift),
+            KeyCode::A if control => value.select_all(),
+            _ => {}
+        }
+    }
+    if !control && !chars.is_empty() {
+        value.insert(&chars);
+    }
+    if value != data.value {
+        data.value = value.clone();
+        let on_change = world
+            .get_non_send_resource::<UiLocals>()
+            .unwrap()
+            .text_inputs[&entity]
+            .clone();
+        on_change.call(world, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextInputValue;
+
+    fn value(text: &str, caret: usize, anchor: Option<usize>) -> TextInputValue {
+        TextInputValue {
+            text: text.to_string(),
+            caret,
+            anchor,
+        }
+    }
+
+    #[test]
+    fn insert_multibyte() {
+        let mut v = value("héllo", 2, None);
+        v.insert("ü");
+        assert_eq!(v, value("héüllo", 3, None));
+
+        v.move_to(0, false);
+        v.insert("😀");
+        assert_eq!(v, value("😀héüllo", 1, None));
+
+        v.move_to(v.len(), false);
+        v.insert("é😀");
+        assert_eq!(v, value("😀héülloé😀", 9, None));
+    }
+
+    #[test]
+    fn backspace_multibyte() {
+        let mut v = TextInputValue::new("h😀é");
+        v.backspace();
+        assert_eq!(v, value("h😀", 2, None));
+        v.backspace();
+        assert_eq!(v, value("h", 1, None));
+        v.backspace();
+        v.backspace();
+        assert_eq!(v, value("", 0, None));
+    }
+
+    #[test]
+    fn delete_multibyte() {
+        let mut v = value("😀é", 0, None);
+        v.delete();
+        assert_eq!(v, value("é", 0, None));
+        v.delete();
+        v.delete();
+        assert_eq!(v, value("", 0, None));
+    }
+
+    #[test]
+    fn replace_selection() {
+        let mut v = value("héllo", 1, None);
+        v.move_to(4, true);
+        assert_eq!(v.selection(), Some(1..4));
+        v.insert("😀y");
+        assert_eq!(v, value("h😀yo", 3, None));
+
+        let mut v = value("h😀éllo", 4, Some(1));
+        v.backspace();
+        assert_eq!(v, value("hlo", 1, None));
+
+        let mut v = value("h😀éllo", 1, Some(3));
+        v.delete();
+        assert_eq!(v, value("hllo", 1, None));
+    }
+
+    #[test]
+    fn clamp_out_of_range_positions() {
+        assert_eq!(value("héllo", 10, Some(20)).selection(), None);
+        assert_eq!(value("héllo", 10, Some(3)).selection(), Some(3..5));
+
+        let mut v = value("héllo", 10, None);
+        v.backspace();
+        assert_eq!(v, value("héll", 4, None));
+
+        let mut v = value("héllo", 10, None);
+        v.delete();
+        assert_eq!(v, value("héllo", 5, None));
+
+        let mut v = value("héllo", 10, Some(3));
+        v.insert("😀");
+        assert_eq!(v, value("hél😀", 4, None));
+
+        let mut v = value("héllo", usize::MAX, None);
+        v.move_right(true);
+        assert_eq!(v, value("héllo", 5, Some(5)));
+        v.move_to(100, true);
+        assert_eq!(v.caret, 5);
+    }
+}
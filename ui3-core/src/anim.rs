@@ -0,0 +1,768 @@
+use std::{rc::Rc, time::Duration};
+
+use crate::{InternalContext, MountedWidgetNode, UiBackend, WidgetNode, WidgetParam};
+
+/// A value that can be animated by [`Tween`] and [`Spring`].
+///
+/// Anything that behaves like a vector works: numbers, and `[f32; N]` for
+/// positions, sizes or colors.
+pub trait Animatable: Clone + PartialEq + 'static {
+    fn add(&self, other: &Self) -> Self;
+    fn scale(&self, factor: f32) -> Self;
+    /// Used to tell when a [`Spring`] has come to rest.
+    fn length(&self) -> f32;
+
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self.scale(1.0 - t).add(&to.scale(t))
+    }
+}
+
+impl Animatable for f32 {
+    fn add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn scale(&self, factor: f32) -> Self {
+        self * factor
+    }
+
+    fn length(&self) -> f32 {
+        self.abs()
+    }
+}
+
+impl Animatable for f64 {
+    fn add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn scale(&self, factor: f32) -> Self {
+        self * factor as f64
+    }
+
+    fn length(&self) -> f32 {
+        self.abs() as f32
+    }
+}
+
+impl<const N: usize> Animatable for [f32; N] {
+    fn add(&self, other: &Self) -> Self {
+        let mut out = *self;
+        out.iter_mut().zip(other).for_each(|(a, b)| *a += b);
+        out
+    }
+
+    fn scale(&self, factor: f32) -> Self {
+        let mut out = *self;
+        out.iter_mut().for_each(|a| *a *= factor);
+        out
+    }
+
+    fn length(&self) -> f32 {
+        self.iter().map(|a| a * a).sum::<f32>().sqrt()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    /// Overshoots the end a little before settling.
+    BackOut,
+    Custom(fn(f32) -> f32),
+}
+
+impl Easing {
+    /// Maps linear progress in `0.0..=1.0` to eased progress.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut if t < 0.5 => 2.0 * t * t,
+            Easing::QuadInOut => 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0,
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut if t < 0.5 => 4.0 * t * t * t,
+            Easing::CubicInOut => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            Easing::BackOut => {
+                let c1 = 1.70158;
+                let c3 = c1 + 1.0;
+                1.0 + c3 * (t - 1.0).powi(3) + c1 * (t - 1.0).powi(2)
+            }
+            Easing::Custom(f) => f(t),
+        }
+    }
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Easing::CubicOut
+    }
+}
+
+pub struct TweenData<T> {
+    from: Option<T>,
+    to: Option<T>,
+    start: Duration,
+    duration: Duration,
+    easing: Easing,
+    rendered_at: Duration,
+}
+
+impl<T: Animatable> TweenData<T> {
+    fn value_at(&self, now: Duration) -> Option<T> {
+        let (from, to) = match (&self.from, &self.to) {
+            (Some(from), Some(to)) => (from, to),
+            (_, to) => return to.clone(),
+        };
+        let elapsed = now.checked_sub(self.start).unwrap_or_default();
+        if elapsed >= self.duration {
+            return Some(to.clone());
+        }
+        let t = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        Some(from.lerp(to, self.easing.apply(t)))
+    }
+}
+
+/// Animates a value over a fixed duration, re-rendering the component every
+/// frame until it arrives.
+///
+/// ```ignore
+/// fn drawer(open: &bool, mut x: Tween<f32>) -> WidgetNode {
+///     let x = x.to(if *open { 0.0 } else { -300.0 }, Duration::from_millis(200), Easing::CubicOut);
+///     ...
+/// }
+/// ```
+pub struct Tween<'s, T> {
+    data: &'s mut TweenData<T>,
+    now: Duration,
+}
+
+impl<'s, T: Animatable> Tween<'s, T> {
+    /// Returns the value to render with. Whenever `target` changes, a new
+    /// animation starts from wherever the value currently is. The first call
+    /// jumps straight to `target`.
+    pub fn to(&mut self, target: T, duration: Duration, easing: Easing) -> T {
+        if self.data.to.as_ref() != Some(&target) {
+            self.data.from = self.data.value_at(self.now);
+            self.data.to = Some(target.clone());
+            self.data.start = self.now;
+            self.data.duration = duration;
+            self.data.easing = easing;
+        }
+        self.data.value_at(self.now).unwrap_or(target)
+    }
+
+    /// Jumps to `value` without animating.
+    pub fn snap(&mut self, value: T) {
+        self.data.from = None;
+        self.data.to = Some(value);
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.data.from.is_none() || self.now >= self.data.start + self.data.duration
+    }
+}
+
+impl<B: UiBackend, T: Animatable> WidgetParam<B> for Tween<'static, T> {
+    type InitData = TweenData<T>;
+    type Item<'ctx, 's> = Tween<'s, T>;
+
+    fn init(_: &mut B::RunCtx<'_>) -> Self::InitData {
+        TweenData {
+            from: None,
+            to: None,
+            start: Duration::default(),
+            duration: Duration::default(),
+            easing: Easing::Linear,
+            rendered_at: Duration::default(),
+        }
+    }
+
+    fn deinit(_: &mut B::RunCtx<'_>, _: Self::InitData) {}
+
+    fn get_item<'ctx, 's>(
+        ctx: &'ctx B::RunCtx<'_>,
+        init_data: &'s mut Self::InitData,
+    ) -> Self::Item<'ctx, 's> {
+        let now = B::now(ctx);
+        init_data.rendered_at = now;
+        Tween {
+            data: init_data,
+            now,
+        }
+    }
+
+    fn needs_recalc(ctx: &B::RunCtx<'_>, init_data: &Self::InitData) -> bool {
+        init_data.from.is_some()
+            && init_data.rendered_at < init_data.start + init_data.duration
+            && B::now(ctx) > init_data.rendered_at
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpringConfig {
+    pub stiffness: f32,
+    pub damping: f32,
+    pub mass: f32,
+    /// How close to the target, in both distance and speed, counts as rest.
+    pub precision: f32,
+}
+
+impl SpringConfig {
+    pub const GENTLE: Self = Self::new(120.0, 14.0);
+    pub const WOBBLY: Self = Self::new(180.0, 12.0);
+    pub const STIFF: Self = Self::new(210.0, 20.0);
+
+    pub const fn new(stiffness: f32, damping: f32) -> Self {
+        Self {
+            stiffness,
+            damping,
+            mass: 1.0,
+            precision: 0.01,
+        }
+    }
+}
+
+impl Default for SpringConfig {
+    fn default() -> Self {
+        Self::new(170.0, 26.0)
+    }
+}
+
+pub struct SpringData<T> {
+    value: Option<T>,
+    velocity: Option<T>,
+    target: Option<T>,
+    config: SpringConfig,
+    last: Duration,
+    settled: bool,
+}
+
+impl<T: Animatable> SpringData<T> {
+    // Fixed substeps keep the simulation stable at low frame rates.
+    const STEP: f32 = 1.0 / 240.0;
+    // Past this, a hitch shouldn't make the spring jump.
+    const MAX_ELAPSED: f32 = 0.25;
+
+    fn step(&mut self, now: Duration) {
+        let elapsed = now.checked_sub(self.last).unwrap_or_default();
+        self.last = now;
+        let (value, velocity, target) = match (&mut self.value, &mut self.velocity, &self.target) {
+            (Some(value), Some(velocity), Some(target)) if !self.settled => {
+                (value, velocity, target)
+            }
+            _ => return,
+        };
+        let elapsed = elapsed.as_secs_f32().min(Self::MAX_ELAPSED);
+        let steps = (elapsed / Self::STEP).ceil().max(1.0);
+        let dt = elapsed / steps;
+        let SpringConfig {
+            stiffness,
+            damping,
+            mass,
+            precision,
+        } = self.config;
+        for _ in 0..steps as u32 {
+            let displacement = value.add(&target.scale(-1.0));
+            let force = displacement
+                .scale(-stiffness)
+                .add(&velocity.scale(-damping));
+            *velocity = velocity.add(&force.scale(dt / mass));
+            *value = value.add(&velocity.scale(dt));
+        }
+        let displacement = value.add(&target.scale(-1.0));
+        if displacement.length() < precision && velocity.length() < precision {
+            *value = target.clone();
+            *velocity = target.scale(0.0);
+            self.settled = true;
+        }
+    }
+}
+
+/// Animates a value with spring physics, re-rendering the component every
+/// frame until it comes to rest. Unlike [`Tween`], changing the target
+/// mid-flight keeps the current velocity.
+pub struct Spring<'s, T> {
+    data: &'s mut SpringData<T>,
+}
+
+impl<'s, T: Animatable> Spring<'s, T> {
+    /// Returns the value to render with, pulling it towards `target`. The
+    /// first call jumps straight to `target`.
+    pub fn to(&mut self, target: T, config: SpringConfig) -> T {
+        let data = &mut *self.data;
+        if data.value.is_none() {
+            data.value = Some(target.clone());
+            data.velocity = Some(target.scale(0.0));
+        } else if data.target.as_ref() != Some(&target) || data.config != config {
+            data.settled = false;
+        }
+        data.target = Some(target.clone());
+        data.config = config;
+        data.value.clone().unwrap_or(target)
+    }
+
+    /// Jumps to `value` and stops moving.
+    pub fn snap(&mut self, value: T) {
+        self.data.velocity = Some(value.scale(0.0));
+        self.data.value = Some(value.clone());
+        self.data.target = Some(value);
+        self.data.settled = true;
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.data.settled
+    }
+}
+
+impl<B: UiBackend, T: Animatable> WidgetParam<B> for Spring<'static, T> {
+    type InitData = SpringData<T>;
+    type Item<'ctx, 's> = Spring<'s, T>;
+
+    fn init(ctx: &mut B::RunCtx<'_>) -> Self::InitData {
+        SpringData {
+            value: None,
+            velocity: None,
+            target: None,
+            config: SpringConfig::default(),
+            last: B::now(ctx),
+            settled: true,
+        }
+    }
+
+    fn deinit(_: &mut B::RunCtx<'_>, _: Self::InitData) {}
+
+    fn get_item<'ctx, 's>(
+        ctx: &'ctx B::RunCtx<'_>,
+        init_data: &'s mut Self::InitData,
+    ) -> Self::Item<'ctx, 's> {
+        init_data.step(B::now(ctx));
+        Spring { data: init_data }
+    }
+
+    fn needs_recalc(ctx: &B::RunCtx<'_>, init_data: &Self::InitData) -> bool {
+        !init_data.settled && B::now(ctx) > init_data.last
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionPhase {
+    Entering,
+    Shown,
+    Exiting,
+}
+
+/// What a [`Transition`] renders its content with.
+#[derive(Clone, Copy, Debug)]
+pub struct TransitionState {
+    pub phase: TransitionPhase,
+    /// How far the content is shown, from `0.0` (gone) to `1.0`, eased.
+    pub progress: f32,
+}
+
+/// Content that animates in when it appears and out when it goes away,
+/// staying mounted until its exit has played.
+///
+/// ```ignore
+/// view! {
+///     { Transition::new(|t| view! { toast(t.progress) }).visible(*show) }
+/// }
+/// ```
+///
+/// The content goes away either when `visible` is set to `false`, or when the
+/// transition is removed from a keyed group, e.g. by a keyed `for` in
+/// [`view!`](crate::view). The latter also works when the transition is what
+/// a keyed component renders. Coming back before the exit has finished plays
+/// the enter from where the exit left off. Transitions are eased with
+/// [`Easing::CubicOut`] unless given another easing.
+pub struct Transition<B: UiBackend> {
+    visible: bool,
+    enter: Duration,
+    exit: Duration,
+    easing: Easing,
+    render: Rc<dyn Fn(TransitionState) -> WidgetNode<B>>,
+}
+
+impl<B: UiBackend> Transition<B> {
+    pub fn new(render: impl Fn(TransitionState) -> WidgetNode<B> + 'static) -> Self {
+        Self {
+            visible: true,
+            enter: Duration::from_millis(250),
+            exit: Duration::from_millis(250),
+            easing: Easing::default(),
+            render: Rc::new(render),
+        }
+    }
+
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Sets both the enter and the exit duration.
+    pub fn duration(self, duration: Duration) -> Self {
+        self.enter(duration).exit(duration)
+    }
+
+    pub fn enter(mut self, duration: Duration) -> Self {
+        self.enter = duration;
+        self
+    }
+
+    pub fn exit(mut self, duration: Duration) -> Self {
+        self.exit = duration;
+        self
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+impl<B: UiBackend> Clone for Transition<B> {
+    fn clone(&self) -> Self {
+        Self {
+            visible: self.visible,
+            enter: self.enter,
+            exit: self.exit,
+            easing: self.easing,
+            render: Rc::clone(&self.render),
+        }
+    }
+}
+
+impl<B: UiBackend> From<Transition<B>> for WidgetNode<B> {
+    fn from(transition: Transition<B>) -> Self {
+        WidgetNode::Transition(transition)
+    }
+}
+
+pub(crate) struct MountedTransition<B: UiBackend> {
+    template: Transition<B>,
+    leaving: bool,
+    // Linear, the easing is applied when rendering.
+    progress: f32,
+    last: Duration,
+    pub(crate) node: Box<MountedWidgetNode<B>>,
+}
+
+impl<B: UiBackend> MountedTransition<B> {
+    pub(crate) fn mount(template: &Transition<B>, ctx: &mut InternalContext<B>) -> Self {
+        let mut this = Self {
+            template: template.clone(),
+            leaving: false,
+            progress: 0.0,
+            last: B::now(ctx.backend_data),
+            node: Box::new(MountedWidgetNode::None),
+        };
+        if this.template.enter == Duration::default() {
+            this.progress = this.target();
+        }
+        this.rerender(ctx);
+        this
+    }
+
+    fn target(&self) -> f32 {
+        if self.template.visible && !self.leaving {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Moves the progress along to `now`, returning whether it changed.
+    fn advance(&mut self, now: Duration) -> bool {
+        let elapsed = now.checked_sub(self.last).unwrap_or_default();
+        self.last = now;
+        let target = self.target();
+        if self.progress == target {
+            return false;
+        }
+        let duration = if target > self.progress {
+            self.template.enter
+        } else {
+            self.template.exit
+        };
+        let step = if duration == Duration::default() {
+            1.0
+        } else {
+            elapsed.as_secs_f32() / duration.as_secs_f32()
+        };
+        self.progress = if target > self.progress {
+            (self.progress + step).min(target)
+        } else {
+            (self.progress - step).max(target)
+        };
+        true
+    }
+
+    fn rerender(&mut self, ctx: &mut InternalContext<B>) {
+        let target = self.target();
+        if self.progress == 0.0 && target == 0.0 {
+            self.node.diff(&WidgetNode::None, ctx);
+            return;
+        }
+        let phase = if self.progress < target {
+            TransitionPhase::Entering
+        } else if self.progress > target {
+            TransitionPhase::Exiting
+        } else {
+            TransitionPhase::Shown
+        };
+        let new = (self.template.render)(TransitionState {
+            phase,
+            progress: self.template.easing.apply(self.progress),
+        });
+        self.node.diff(&new, ctx);
+    }
+
+    pub(crate) fn process(&mut self, ctx: &mut InternalContext<B>) {
+        if self.advance(B::now(ctx.backend_data)) {
+            self.rerender(ctx);
+        } else {
+            self.node.process(ctx);
+        }
+    }
+
+    pub(crate) fn diff(&mut self, new: &Transition<B>, ctx: &mut InternalContext<B>) {
+        self.template = new.clone();
+        self.advance(B::now(ctx.backend_data));
+        self.rerender(ctx);
+    }
+
+    pub(crate) fn unmount(self, ctx: &mut InternalContext<B>) {
+        self.node.unmount(ctx);
+    }
+
+    /// Marks the transition as removed from its group, which plays the exit
+    /// regardless of `visible`.
+    pub(crate) fn set_leaving(&mut self, leaving: bool) {
+        self.leaving = leaving;
+    }
+
+    pub(crate) fn has_left(&self) -> bool {
+        self.leaving && self.progress == 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc, time::Duration};
+
+    use super::{Easing, Spring, SpringConfig, Transition, Tween};
+    use crate::{
+        test_backend::{leaf, set, units, Setter, TestBackend, TestCtx},
+        Application, State, WidgetFunc, WidgetNode,
+    };
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn easing_endpoints() {
+        let easings = [
+            Easing::Linear,
+            Easing::QuadIn,
+            Easing::QuadOut,
+            Easing::QuadInOut,
+            Easing::CubicIn,
+            Easing::CubicOut,
+            Easing::CubicInOut,
+            Easing::BackOut,
+        ];
+        for easing in easings {
+            assert!(easing.apply(0.0).abs() < 1e-6, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-6, "{:?}", easing);
+            assert_eq!(easing.apply(-1.0), easing.apply(0.0), "{:?}", easing);
+            assert_eq!(easing.apply(2.0), easing.apply(1.0), "{:?}", easing);
+        }
+        assert!(matches!(Easing::default(), Easing::CubicOut));
+    }
+
+    fn tweened(
+        setter: &Setter<f32>,
+        target: State<f32, TestBackend>,
+        mut tween: Tween<f32>,
+    ) -> WidgetNode<TestBackend> {
+        setter.set(Some(target.setter()));
+        let value = tween.to(*target, ms(100), Easing::CubicOut);
+        leaf(&format!("{} {}", value, tween.is_settled()))
+    }
+
+    #[test]
+    fn tween_progress() {
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(tweened.w((Rc::clone(&setter),)), &mut ctx);
+        assert_eq!(units(&app), ["0 true"]);
+
+        set(&setter, &mut ctx, 100.0);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["0 false"]);
+
+        ctx.now = ms(50);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["87.5 false"]);
+
+        ctx.now = ms(100);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["100 true"]);
+        app.unmount(&mut ctx);
+    }
+
+    fn sprung(
+        setter: &Setter<f32>,
+        out: &Rc<Cell<(f32, bool)>>,
+        target: State<f32, TestBackend>,
+        mut spring: Spring<f32>,
+    ) -> WidgetNode<TestBackend> {
+        setter.set(Some(target.setter()));
+        out.set((
+            spring.to(*target, SpringConfig::default()),
+            spring.is_settled(),
+        ));
+        WidgetNode::None
+    }
+
+    #[test]
+    fn spring_settles_on_target() {
+        let setter = Setter::default();
+        let out = Rc::new(Cell::new((0.0, false)));
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(sprung.w((Rc::clone(&setter), Rc::clone(&out))), &mut ctx);
+        assert_eq!(out.get(), (0.0, true));
+
+        set(&setter, &mut ctx, 1.0);
+        app.update(&mut ctx);
+        let mut frames = 0;
+        while !out.get().1 {
+            frames += 1;
+            assert!(frames < 200, "spring didn't settle");
+            ctx.now += ms(16);
+            app.update(&mut ctx);
+            let (value, _) = out.get();
+            assert!(value > 0.0 && value < 1.1, "{}", value);
+        }
+        assert!(frames > 5);
+        assert_eq!(out.get(), (1.0, true));
+        app.unmount(&mut ctx);
+    }
+
+    fn fading(k: &'static str) -> WidgetNode<TestBackend> {
+        Transition::new(move |t| leaf(&format!("{}:{:?}:{}", k, t.phase, t.progress)))
+            .enter(Duration::default())
+            .exit(ms(100))
+            .easing(Easing::Linear)
+            .into()
+    }
+
+    fn faders(
+        setter: &Setter<Vec<&'static str>>,
+        keys: State<Vec<&'static str>, TestBackend>,
+    ) -> WidgetNode<TestBackend> {
+        setter.set(Some(keys.setter()));
+        keys.iter().map(|k| fading(k).keyed(*k)).collect()
+    }
+
+    #[test]
+    fn removed_transition_exits_then_unmounts() {
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(faders.w((Rc::clone(&setter),)), &mut ctx);
+        set(&setter, &mut ctx, vec!["a", "b", "c"]);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["a:Shown:1", "b:Shown:1", "c:Shown:1"]);
+
+        set(&setter, &mut ctx, vec!["a", "c"]);
+        app.update(&mut ctx);
+
+        ctx.now = ms(50);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["a:Shown:1", "b:Exiting:0.5", "c:Shown:1"]);
+
+        ctx.now = ms(100);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["a:Shown:1", "c:Shown:1"]);
+
+        ctx.now = ms(200);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["a:Shown:1", "c:Shown:1"]);
+        app.unmount(&mut ctx);
+    }
+
+    fn toggled(setter: &Setter<bool>, hidden: State<bool, TestBackend>) -> WidgetNode<TestBackend> {
+        setter.set(Some(hidden.setter()));
+        Transition::new(|t| leaf(&format!("{:?}:{}", t.phase, t.progress)))
+            .duration(ms(100))
+            .easing(Easing::Linear)
+            .visible(!*hidden)
+            .into()
+    }
+
+    #[test]
+    fn reenter_during_exit() {
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(toggled.w((Rc::clone(&setter),)), &mut ctx);
+        assert_eq!(units(&app), ["Entering:0"]);
+
+        ctx.now = ms(100);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["Shown:1"]);
+
+        set(&setter, &mut ctx, true);
+        app.update(&mut ctx);
+        ctx.now = ms(150);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["Exiting:0.5"]);
+
+        // Picks up from where the exit got to.
+        set(&setter, &mut ctx, false);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["Entering:0.5"]);
+
+        ctx.now = ms(200);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["Shown:1"]);
+
+        set(&setter, &mut ctx, true);
+        app.update(&mut ctx);
+        ctx.now = ms(300);
+        app.update(&mut ctx);
+        assert!(units(&app).is_empty());
+        app.unmount(&mut ctx);
+    }
+
+    #[test]
+    fn keyed_reenter_during_exit() {
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(faders.w((Rc::clone(&setter),)), &mut ctx);
+        set(&setter, &mut ctx, vec!["a", "b"]);
+        app.update(&mut ctx);
+        set(&setter, &mut ctx, vec!["a"]);
+        app.update(&mut ctx);
+        ctx.now = ms(50);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["a:Shown:1", "b:Exiting:0.5"]);
+
+        set(&setter, &mut ctx, vec!["a", "b"]);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["a:Shown:1", "b:Shown:1"]);
+
+        ctx.now = ms(200);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["a:Shown:1", "b:Shown:1"]);
+        app.unmount(&mut ctx);
+    }
+}
@@ -0,0 +1,139 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{UiBackend, WidgetParam};
+
+type CallbackFn<B, Args> = dyn Fn(&mut <B as UiBackend>::RunCtx<'_>, Args);
+
+/// An event handler that can be handed to backend units.
+///
+/// Clones share their identity, so comparing two callbacks tells whether
+/// they're the same handler. Get one from [`UseCallback`] to keep the
+/// identity stable across re-renders.
+pub struct Callback<B: UiBackend, Args = ()>(Rc<RefCell<Rc<CallbackFn<B, Args>>>>);
+
+impl<B: UiBackend, Args> Callback<B, Args> {
+    /// Creates a standalone callback, with a new identity each time.
+    pub fn new(f: impl Fn(&mut B::RunCtx<'_>, Args) + 'static) -> Self {
+        Self(Rc::new(RefCell::new(Rc::new(f))))
+    }
+
+    pub fn call(&self, ctx: &mut B::RunCtx<'_>, args: Args) {
+        // Cloned out so the callback can replace itself while running.
+        let f = Rc::clone(&*self.0.borrow());
+        f(ctx, args)
+    }
+}
+
+impl<B: UiBackend, Args> Clone for Callback<B, Args> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<B: UiBackend, Args> PartialEq for Callback<B, Args> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Keeps a [`Callback`] with the same identity for the whole lifetime of the
+/// component, always invoking the closure passed in the latest render.
+///
+/// ```ignore
+/// fn counter(count: Store<u32, BevyBackend>, on_click: UseCallback<BevyBackend>) -> WidgetNode {
+///     let id = count.id();
+///     let on_click = on_click.set(move |world, ()| *id.access_mut(world) += 1);
+///     ...
+/// }
+/// ```
+pub struct UseCallback<'s, B: UiBackend, Args = ()> {
+    callback: &'s Callback<B, Args>,
+}
+
+impl<'s, B: UiBackend, Args> UseCallback<'s, B, Args> {
+    pub fn set(&self, f: impl Fn(&mut B::RunCtx<'_>, Args) + 'static) -> Callback<B, Args> {
+        *self.callback.0.borrow_mut() = Rc::new(f);
+        self.callback.clone()
+    }
+}
+
+impl<B: UiBackend, Args: 'static> WidgetParam<B> for UseCallback<'static, B, Args> {
+    type InitData = Callback<B, Args>;
+    type Item<'ctx, 's> = UseCallback<'s, B, Args>;
+
+    fn init(_: &mut B::RunCtx<'_>) -> Self::InitData {
+        Callback::new(|_, _| {})
+    }
+
+    fn deinit(_: &mut B::RunCtx<'_>, init_data: Self::InitData) {
+        // Units may still hold on to the callback, but whatever it captured
+        // shouldn't outlive the component.
+        *init_data.0.borrow_mut() = Rc::new(|_, _| {});
+    }
+
+    fn get_item<'ctx, 's>(
+        _: &'ctx B::RunCtx<'_>,
+        init_data: &'s mut Self::InitData,
+    ) -> Self::Item<'ctx, 's> {
+        UseCallback {
+            callback: init_data,
+        }
+    }
+
+    fn needs_recalc(_: &B::RunCtx<'_>, _: &Self::InitData) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::{Callback, UseCallback};
+    use crate::{
+        test_backend::{leaf, set, Setter, TestBackend, TestCtx},
+        Application, State, WidgetFunc, WidgetNode,
+    };
+
+    type Handles = Rc<RefCell<Vec<Callback<TestBackend, u32>>>>;
+
+    fn button(
+        handles: &Handles,
+        calls: &Rc<RefCell<Vec<(u32, u32)>>>,
+        setter: &Setter<u32>,
+        round: State<u32, TestBackend>,
+        on_click: UseCallback<TestBackend, u32>,
+    ) -> WidgetNode<TestBackend> {
+        setter.set(Some(round.setter()));
+        let (round, calls) = (*round, Rc::clone(calls));
+        let on_click = on_click.set(move |_, arg| calls.borrow_mut().push((round, arg)));
+        handles.borrow_mut().push(on_click);
+        leaf("button")
+    }
+
+    #[test]
+    fn handle_is_stable_and_calls_latest_closure() {
+        let handles = Handles::default();
+        let calls = Rc::default();
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(
+            button.w((Rc::clone(&handles), Rc::clone(&calls), Rc::clone(&setter))),
+            &mut ctx,
+        );
+        set(&setter, &mut ctx, 1);
+        app.update(&mut ctx);
+        set(&setter, &mut ctx, 2);
+        app.update(&mut ctx);
+
+        let handles = handles.borrow();
+        assert_eq!(handles.len(), 3);
+        assert!(handles.iter().all(|h| Rc::ptr_eq(&h.0, &handles[0].0)));
+        handles[0].call(&mut ctx, 7);
+        assert_eq!(*calls.borrow(), [(2, 7)]);
+
+        app.unmount(&mut ctx);
+        handles[0].call(&mut ctx, 8);
+        assert_eq!(*calls.borrow(), [(2, 7)]);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{NodeRef, RenderNode, RenderRoots, UiBackend, UnitId, WidgetParam};
+
+/// The units that can take keyboard focus, in the order tab moves through
+/// them.
+///
+/// That's render order: parents before their children, siblings in order, the
+/// main tree first and then the portals, sorted by target. Which units are
+/// focusable is up to [`UiBackend::is_focusable`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FocusOrder(Vec<UnitId>);
+
+impl FocusOrder {
+    pub fn new<B: UiBackend>(roots: &RenderRoots<B>) -> Self {
+        fn walk<B: UiBackend>(nodes: &[RenderNode<B>], order: &mut Vec<UnitId>) {
+            for node in nodes {
+                if B::is_focusable(node.unit) {
+                    order.push(node.id);
+                }
+                walk(&node.children, order);
+            }
+        }
+
+        let mut order = vec![];
+        walk(&roots.main, &mut order);
+        for nodes in roots.portals.values() {
+            walk(nodes, &mut order);
+        }
+        Self(order)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = UnitId> + '_ {
+        self.0.iter().copied()
+    }
+
+    pub fn contains(&self, id: UnitId) -> bool {
+        self.0.contains(&id)
+    }
+
+    /// The unit after `current`, wrapping around. Without a current unit, or
+    /// if it isn't focusable anymore, that's the first one.
+    pub fn next(&self, current: Option<UnitId>) -> Option<UnitId> {
+        let index = current.and_then(|id| self.0.iter().position(|&i| i == id));
+        match index {
+            Some(index) => self.0.get((index + 1) % self.0.len()).copied(),
+            None => self.0.first().copied(),
+        }
+    }
+
+    /// The unit before `current`, wrapping around. Without a current unit, or
+    /// if it isn't focusable anymore, that's the last one.
+    pub fn prev(&self, current: Option<UnitId>) -> Option<UnitId> {
+        let index = current.and_then(|id| self.0.iter().position(|&i| i == id));
+        match index {
+            Some(index) => self
+                .0
+                .get((index + self.0.len() - 1) % self.0.len())
+                .copied(),
+            None => self.0.last().copied(),
+        }
+    }
+}
+
+/// Which unit has keyboard focus, as tracked by the backend.
+///
+/// ```ignore
+/// fn search_bar(focus: Focus<BevyBackend>, input: NodeRef) -> WidgetNode {
+///     let color = if focus.is(&input) { Color::WHITE } else { Color::GRAY };
+///     ...
+/// }
+/// ```
+///
+/// The component re-renders whenever the focus moves. To move it from a
+/// callback, use [`Focus::set`].
+pub struct Focus<B: UiBackend> {
+    focused: Option<B::NodeId>,
+}
+
+impl<B: UiBackend> Focus<B> {
+    pub fn get(&self) -> Option<B::NodeId> {
+        self.focused
+    }
+
+    /// Whether the unit tagged with `node_ref` has focus.
+    pub fn is(&self, node_ref: &NodeRef<B>) -> bool {
+        self.focused.is_some() && self.focused == node_ref.get()
+    }
+
+    /// Focuses the unit tagged with `node_ref`, or nothing if it isn't
+    /// rendered.
+    pub fn set(ctx: &mut B::RunCtx<'_>, node_ref: &NodeRef<B>) {
+        B::set_focused(ctx, node_ref.get());
+    }
+
+    pub fn clear(ctx: &mut B::RunCtx<'_>) {
+        B::set_focused(ctx, None);
+    }
+}
+
+impl<B: UiBackend> WidgetParam<B> for Focus<B> {
+    /// The focused unit as of the last render.
+    type InitData = Option<B::NodeId>;
+    type Item<'ctx, 's> = Focus<B>;
+
+    fn init(ctx: &mut B::RunCtx<'_>) -> Self::InitData {
+        B::focused(ctx)
+    }
+
+    fn deinit(_: &mut B::RunCtx<'_>, _: Self::InitData) {}
+
+    fn get_item<'ctx, 's>(
+        ctx: &'ctx B::RunCtx<'_>,
+        init_data: &'s mut Self::InitData,
+    ) -> Self::Item<'ctx, 's> {
+        *init_data = B::focused(ctx);
+        Focus {
+            focused: *init_data,
+        }
+    }
+
+    fn needs_recalc(ctx: &B::RunCtx<'_>, init_data: &Self::InitData) -> bool {
+        B::focused(ctx) != *init_data
+    }
+}
@@ -0,0 +1,282 @@
+use std::{fmt, iter::FromIterator};
+
+use crate::{IntOrString, UiBackend, WidgetNode, WidgetNodeGroup};
+
+/// Returned when a [`WidgetNodeGroup`] is given the same key twice.
+#[derive(Debug, Clone)]
+pub struct DuplicateKeyError {
+    pub key: String,
+}
+
+impl fmt::Display for DuplicateKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "key `{}` used multiple times in the same group",
+            self.key
+        )
+    }
+}
+
+impl std::error::Error for DuplicateKeyError {}
+
+/// A node paired with the key it should be mounted under, see [`WidgetNode::keyed`].
+///
+/// Collecting an iterator of these into a `WidgetNodeGroup<B>` (or
+/// `WidgetNode<B>`) builds a keyed group. Keys are expected to be unique: a
+/// repeated key trips a debug assertion, and only the last node using it is
+/// kept. Collecting into a `Result<WidgetNodeGroup<B>, _>` instead fails on
+/// the first repeated key.
+pub struct Keyed<B: UiBackend> {
+    pub key: String,
+    pub node: WidgetNode<B>,
+}
+
+impl<B: UiBackend> WidgetNode<B> {
+    pub fn keyed(self, key: impl Into<String>) -> Keyed<B> {
+        Keyed {
+            key: key.into(),
+            node: self,
+        }
+    }
+}
+
+impl<B: UiBackend> From<WidgetNodeGroup<B>> for WidgetNode<B> {
+    fn from(group: WidgetNodeGroup<B>) -> Self {
+        WidgetNode::Group(group)
+    }
+}
+
+impl<B: UiBackend, T: Into<WidgetNode<B>>> From<Option<T>> for WidgetNode<B> {
+    fn from(node: Option<T>) -> Self {
+        node.map_or(WidgetNode::None, Into::into)
+    }
+}
+
+impl<B: UiBackend> FromIterator<WidgetNode<B>> for WidgetNodeGroup<B> {
+    fn from_iter<I: IntoIterator<Item = WidgetNode<B>>>(iter: I) -> Self {
+        let mut group = Self::default();
+        iter.into_iter().for_each(|node| group.push(node));
+        group
+    }
+}
+
+impl<B: UiBackend> FromIterator<WidgetNode<B>> for WidgetNode<B> {
+    fn from_iter<I: IntoIterator<Item = WidgetNode<B>>>(iter: I) -> Self {
+        WidgetNode::Group(iter.into_iter().collect())
+    }
+}
+
+impl<B: UiBackend> FromIterator<Keyed<B>> for WidgetNodeGroup<B> {
+    fn from_iter<I: IntoIterator<Item = Keyed<B>>>(iter: I) -> Self {
+        let mut group = Self::default();
+        for Keyed { key, node } in iter {
+            debug_assert!(
+                !group.named.contains_key(&key),
+                "{}",
+                DuplicateKeyError { key: key.clone() }
+            );
+            let entry = IntOrString::String(key.clone());
+            if group.named.insert(key, node).is_some() {
+                // Rendered where the last node with the key is.
+                group.render_order.retain(|e| *e != entry);
+            }
+            group.render_order.push(entry);
+        }
+        group
+    }
+}
+
+impl<B: UiBackend> FromIterator<Keyed<B>> for WidgetNode<B> {
+    fn from_iter<I: IntoIterator<Item = Keyed<B>>>(iter: I) -> Self {
+        WidgetNode::Group(iter.into_iter().collect())
+    }
+}
+
+impl<B: UiBackend> FromIterator<Keyed<B>> for Result<WidgetNodeGroup<B>, DuplicateKeyError> {
+    fn from_iter<I: IntoIterator<Item = Keyed<B>>>(iter: I) -> Self {
+        let mut group = WidgetNodeGroup::default();
+        for Keyed { key, node } in iter {
+            group.push_named(node, key)?;
+        }
+        Ok(group)
+    }
+}
+
+impl<B: UiBackend> FromIterator<Keyed<B>> for Result<WidgetNode<B>, DuplicateKeyError> {
+    fn from_iter<I: IntoIterator<Item = Keyed<B>>>(iter: I) -> Self {
+        iter.into_iter()
+            .collect::<Result<WidgetNodeGroup<B>, _>>()
+            .map(WidgetNode::Group)
+    }
+}
+
+macro_rules! impl_tuple_groups {
+    () => {};
+    ($head: ident $(, $tail: ident)*) => {
+        impl_tuple_groups!(@impl $head $(, $tail)*);
+        impl_tuple_groups!($($tail),*);
+    };
+    (@impl $($idents: ident),*) => {
+        #[allow(non_snake_case)]
+        impl<B: UiBackend, $($idents: Into<WidgetNode<B>>,)*> From<($($idents,)*)>
+            for WidgetNodeGroup<B>
+        {
+            fn from(($($idents,)*): ($($idents,)*)) -> Self {
+                let mut group = Self::default();
+                $(group.push($idents.into());)*
+                group
+            }
+        }
+
+        impl<B: UiBackend, $($idents: Into<WidgetNode<B>>,)*> From<($($idents,)*)>
+            for WidgetNode<B>
+        {
+            fn from(tuple: ($($idents,)*)) -> Self {
+                WidgetNode::Group(tuple.into())
+            }
+        }
+    };
+}
+
+impl_tuple_groups!(_0, _1, _2, _3, _4, _5, _6, _7, _8, _9, _10, _11);
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+    use crate::{
+        test_backend::{leaf, set, units, Setter, TestBackend, TestCtx},
+        view, Application, SetState, State, WidgetFunc, WidgetNode, WidgetNodeGroup,
+    };
+
+    #[test]
+    fn duplicate_keys() {
+        let group: Result<WidgetNodeGroup<TestBackend>, _> = vec![
+            leaf("a").keyed("x"),
+            leaf("b").keyed("y"),
+            leaf("c").keyed("x"),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(group.err().map(|err| err.key), Some("x".to_string()));
+
+        let mut group = WidgetNodeGroup::<TestBackend>::default();
+        group.push_named(leaf("a"), "x").unwrap();
+        let err = group.push_named(leaf("b"), "x").unwrap_err();
+        assert_eq!(err.key, "x");
+        assert_eq!(
+            err.to_string(),
+            "key `x` used multiple times in the same group"
+        );
+    }
+
+    type Counters = Rc<RefCell<HashMap<String, SetState<u32, TestBackend>>>>;
+
+    fn counter(
+        name: &String,
+        counters: &Counters,
+        count: State<u32, TestBackend>,
+    ) -> WidgetNode<TestBackend> {
+        counters.borrow_mut().insert(name.clone(), count.setter());
+        leaf(&format!("{}{}", name, *count))
+    }
+
+    fn counters(
+        counters: &Counters,
+        setter: &Setter<bool>,
+        reversed: State<bool, TestBackend>,
+    ) -> WidgetNode<TestBackend> {
+        setter.set(Some(reversed.setter()));
+        let mut names = vec!["a", "b", "c"];
+        if *reversed {
+            names.reverse();
+        }
+        names
+            .into_iter()
+            .map(|name| {
+                counter
+                    .w((name.to_string(), Rc::clone(counters)))
+                    .keyed(name)
+            })
+            .collect::<Result<WidgetNode<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn reorder_keeps_keyed_state() {
+        let shared = Counters::default();
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(
+            counters.w((Rc::clone(&shared), Rc::clone(&setter))),
+            &mut ctx,
+        );
+        shared.borrow()["a"].set(&mut ctx, 1);
+        shared.borrow()["c"].set(&mut ctx, 3);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["a1", "b0", "c3"]);
+
+        set(&setter, &mut ctx, true);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["c3", "b0", "a1"]);
+        app.unmount(&mut ctx);
+    }
+
+    fn view_counters(
+        counters: &Counters,
+        setter: &Setter<bool>,
+        reversed: State<bool, TestBackend>,
+    ) -> WidgetNode<TestBackend> {
+        setter.set(Some(reversed.setter()));
+        let mut names = vec!["a", "b", "c"];
+        if *reversed {
+            names.reverse();
+        }
+        view! { crate = crate;
+            String::from("list") {
+                for name in names key name {
+                    counter(name.to_string(), Rc::clone(counters))
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn keyed_for_in_component() {
+        let shared = Counters::default();
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(
+            view_counters.w((Rc::clone(&shared), Rc::clone(&setter))),
+            &mut ctx,
+        );
+        shared.borrow()["b"].set(&mut ctx, 2);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["list", "a0", "b2", "c0"]);
+
+        set(&setter, &mut ctx, true);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["list", "c0", "b2", "a0"]);
+        app.unmount(&mut ctx);
+    }
+
+    #[test]
+    #[cfg_attr(
+        debug_assertions,
+        should_panic(expected = "key `x` used multiple times")
+    )]
+    fn repeated_key_keeps_last_node() {
+        let node: WidgetNode<TestBackend> = vec![
+            leaf("a").keyed("x"),
+            leaf("b").keyed("y"),
+            leaf("c").keyed("x"),
+        ]
+        .into_iter()
+        .collect();
+        let mut ctx = TestCtx::default();
+        let app = Application::new(node, &mut ctx);
+        assert_eq!(units(&app), ["b", "c"]);
+        app.unmount(&mut ctx);
+    }
+}
@@ -1,15 +1,51 @@
 #![feature(hash_drain_filter)]
 #![feature(generic_associated_types)]
 
+mod anim;
+mod callback;
+mod focus;
+mod group;
+mod node_ref;
+mod resource;
+mod state;
+#[cfg(test)]
+mod test_backend;
+mod timer;
+mod virtual_list;
+
+pub use anim::{
+    Animatable, Easing, Spring, SpringConfig, SpringData, Transition, TransitionPhase,
+    TransitionState, Tween, TweenData,
+};
+pub use callback::{Callback, UseCallback};
+pub use focus::{Focus, FocusOrder};
+pub use group::{DuplicateKeyError, Keyed};
+pub use node_ref::NodeRef;
+pub use resource::{AsyncResource, AsyncResourceData, AsyncState};
+pub use state::{SetState, State, StateInitData, StateSlots};
+pub use timer::{Interval, IntervalData, Timer, TimerData};
+pub use ui3_macros::{component, view};
+pub use virtual_list::{ItemExtent, VirtualList};
+
+use anim::MountedTransition;
+use virtual_list::MountedVirtualList;
+
 use std::{
     any::{Any, TypeId},
     cell::{Cell, RefCell},
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     marker::PhantomData,
     ops::DerefMut,
     rc::Rc,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
 };
 
+/// A mounted widget tree.
+///
+/// An application should be torn down with [`Application::unmount`]. Its
+/// stores and effect cleanups need the run context to be released, so
+/// dropping it without unmounting leaks them.
 pub struct Application<B: UiBackend> {
     root: MountedWidgetNode<B>,
 }
@@ -52,14 +88,53 @@ impl<B: UiBackend> Application<B> {
         this
     }
 
-    pub fn render(&self) -> Vec<RenderNode<B>> {
-        self.root.render()
+    pub fn render(&self) -> RenderRoots<B> {
+        let mut portals = BTreeMap::new();
+        let main = self.root.render(&mut portals);
+        RenderRoots { main, portals }
+    }
+
+    /// Tears down the whole tree: every mounted component has its params
+    /// deinitialized and every effect has its pending cleanup run.
+    pub fn unmount(mut self, ctx: &mut B::RunCtx<'_>) {
+        let mut execute_at_end = vec![];
+        let mut internal = InternalContext {
+            backend_data: &mut *ctx,
+            execute_at_end: &mut execute_at_end,
+        };
+        std::mem::replace(&mut self.root, MountedWidgetNode::None).unmount(&mut internal);
+        execute_at_end.drain(..).for_each(|f| f(ctx));
+        B::mark_update(ctx);
     }
 }
 
+/// The output of [`Application::render`]: the main tree, plus the trees
+/// mounted through [`WidgetNode::Portal`]s, grouped by their target root and
+/// sorted by target.
+pub struct RenderRoots<'a, B: UiBackend> {
+    pub main: Vec<RenderNode<'a, B>>,
+    pub portals: BTreeMap<&'a str, Vec<RenderNode<'a, B>>>,
+}
+
 pub struct RenderNode<'a, B: UiBackend> {
+    pub id: UnitId,
     pub unit: &'a B::Unit,
     pub children: Vec<RenderNode<'a, B>>,
+    /// Should be filled in with the backend's handle to this unit.
+    pub node_ref: Option<&'a NodeRef<B>>,
+}
+
+/// Identifies a mounted unit for as long as it stays mounted, even as its
+/// contents change, so backends can keep their own objects for it around
+/// between renders instead of recreating them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct UnitId(u64);
+
+impl UnitId {
+    fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
 }
 
 #[doc(hidden)]
@@ -78,6 +153,7 @@ pub trait WidgetFunc<P: 'static, B: UiBackend, Marker>: 'static {
     fn init(&self, ctx: &mut InternalContext<B>) -> Box<dyn Any>;
     fn deinit(&self, ctx: &mut InternalContext<B>, init_data: Box<dyn Any>);
     fn needs_recalc(&self, ctx: &InternalContext<B>, init_data: &dyn Any) -> bool;
+    fn is_pending(&self, init_data: &dyn Any) -> bool;
     fn as_dynamic(&self) -> Rc<dyn DynWidgetFunc<B>>;
     fn fn_type_id(&self) -> TypeId;
     fn w(self, props: P) -> WidgetNode<B>
@@ -99,6 +175,9 @@ pub trait EffectFunc<P: 'static, B: UiBackend, Marker>: 'static {
         init_data: &mut dyn Any,
     ) -> Box<dyn FnOnce(&mut B::RunCtx<'_>)>;
     fn init(&self, ctx: &mut B::RunCtx<'_>) -> Rc<RefCell<dyn Any>>;
+    /// Releases what [`init`](Self::init) set up, once the effect unmounts
+    /// and its last cleanup has run.
+    fn deinit(&self, _ctx: &mut B::RunCtx<'_>, _init_data: Rc<RefCell<dyn Any>>) {}
     fn needs_recalc(&self, ctx: &B::RunCtx<'_>, init_data: &dyn Any) -> bool;
     fn as_dynamic(&self) -> Rc<dyn DynEffectFunc<B>>;
     fn fn_type_id(&self) -> TypeId;
@@ -121,6 +200,7 @@ pub trait DynWidgetFunc<B: UiBackend>: 'static {
         init_data: &mut dyn Any,
     ) -> WidgetNode<B>;
     fn needs_recalc(&self, ctx: &InternalContext<B>, init_data: &dyn Any) -> bool;
+    fn is_pending(&self, init_data: &dyn Any) -> bool;
     fn init(&self, ctx: &mut InternalContext<B>) -> Box<dyn Any>;
     fn deinit(&self, ctx: &mut InternalContext<B>, init_data: Box<dyn Any>);
     fn fn_type_id(&self) -> TypeId;
@@ -134,6 +214,7 @@ pub trait DynEffectFunc<B: UiBackend>: 'static {
     ) -> Box<dyn FnOnce(&mut B::RunCtx<'_>)>;
     fn needs_recalc(&self, ctx: &B::RunCtx<'_>, init_data: &dyn Any) -> bool;
     fn init(&self, ctx: &mut B::RunCtx<'_>) -> Rc<RefCell<dyn Any>>;
+    fn deinit(&self, ctx: &mut B::RunCtx<'_>, init_data: Rc<RefCell<dyn Any>>);
     fn fn_type_id(&self) -> TypeId;
 }
 
@@ -153,6 +234,10 @@ impl<P: 'static, B: UiBackend, Params: 'static> DynWidgetFunc<B>
         (**self).needs_recalc(ctx, init_data)
     }
 
+    fn is_pending(&self, init_data: &dyn Any) -> bool {
+        (**self).is_pending(init_data)
+    }
+
     fn fn_type_id(&self) -> TypeId {
         (**self).fn_type_id()
     }
@@ -189,6 +274,10 @@ impl<P: 'static, B: UiBackend, Params: 'static> DynEffectFunc<B>
     fn init(&self, stores: &mut B::RunCtx<'_>) -> Rc<RefCell<dyn Any>> {
         (**self).init(stores)
     }
+
+    fn deinit(&self, ctx: &mut B::RunCtx<'_>, init_data: Rc<RefCell<dyn Any>>) {
+        (**self).deinit(ctx, init_data)
+    }
 }
 
 pub struct WidgetComponent<B: UiBackend> {
@@ -226,10 +315,33 @@ impl<B: UiBackend> WidgetNode<B> {
             WidgetNode::Component(c) => MountedWidgetNode::Component(c.mount(ctx)),
             WidgetNode::Effect(c) => MountedWidgetNode::Effect(c.mount(ctx)),
             WidgetNode::Unit { children, unit } => MountedWidgetNode::Unit {
+                id: UnitId::next(),
                 unit: unit.clone(),
                 children: Box::new(children.mount(ctx)),
             },
             WidgetNode::Group(group) => MountedWidgetNode::Group(group.mount(ctx)),
+            WidgetNode::Portal { target, children } => MountedWidgetNode::Portal {
+                target: target.clone(),
+                children: Box::new(children.mount(ctx)),
+            },
+            WidgetNode::Children(c) => MountedWidgetNode::Children {
+                source: c.clone(),
+                node: Box::new(c.0.mount(ctx)),
+            },
+            WidgetNode::Ref { node_ref, node } => MountedWidgetNode::Ref {
+                node_ref: node_ref.clone(),
+                node: Box::new(node.mount(ctx)),
+            },
+            WidgetNode::Suspense { fallback, content } => MountedWidgetNode::Suspense {
+                fallback: Box::new(fallback.mount(ctx)),
+                content: Box::new(content.mount(ctx)),
+            },
+            WidgetNode::Transition(t) => {
+                MountedWidgetNode::Transition(MountedTransition::mount(t, ctx))
+            }
+            WidgetNode::Virtual(list) => {
+                MountedWidgetNode::Virtual(MountedVirtualList::mount(list, ctx))
+            }
         }
     }
 }
@@ -270,6 +382,80 @@ pub enum WidgetNode<B: UiBackend> {
         children: Rc<WidgetNode<B>>,
     },
     Group(WidgetNodeGroup<B>),
+    /// Mounts `children` as part of this tree, but renders them under the
+    /// secondary root named `target` instead of in place.
+    Portal {
+        target: String,
+        children: Rc<WidgetNode<B>>,
+    },
+    /// A subtree passed in through props, see [`Children`].
+    Children(Children<B>),
+    /// See [`WidgetNode::with_ref`].
+    Ref {
+        node_ref: NodeRef<B>,
+        node: Rc<WidgetNode<B>>,
+    },
+    /// Renders `fallback` instead of `content` while anything in `content`
+    /// is pending, such as an [`AsyncResource`] that hasn't resolved yet.
+    /// `content` stays mounted the whole time, so it keeps its state.
+    Suspense {
+        fallback: Rc<WidgetNode<B>>,
+        content: Rc<WidgetNode<B>>,
+    },
+    /// See [`Transition`].
+    Transition(Transition<B>),
+    /// See [`VirtualList`].
+    Virtual(VirtualList<B>),
+}
+
+/// A subtree a component receives as a prop and places somewhere in its own
+/// output, for writing container components.
+///
+/// ```ignore
+/// fn panel(children: &Children<BevyBackend>) -> WidgetNode {
+///     view! { Unit::node(panel_style(), Color::GRAY) { { children.clone() } } }
+/// }
+/// ```
+///
+/// Wherever it's placed, the subtree is diffed in place against the previous
+/// children instead of being remounted. When the container re-renders with the
+/// same `Children` it was given before, the subtree is left alone entirely.
+pub struct Children<B: UiBackend>(Rc<WidgetNode<B>>);
+
+impl<B: UiBackend> Children<B> {
+    pub fn new(node: WidgetNode<B>) -> Self {
+        Self(Rc::new(node))
+    }
+}
+
+impl<B: UiBackend> Clone for Children<B> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<B: UiBackend> Default for Children<B> {
+    fn default() -> Self {
+        Self::new(WidgetNode::None)
+    }
+}
+
+impl<B: UiBackend> PartialEq for Children<B> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<B: UiBackend> From<WidgetNode<B>> for Children<B> {
+    fn from(node: WidgetNode<B>) -> Self {
+        Self::new(node)
+    }
+}
+
+impl<B: UiBackend> From<Children<B>> for WidgetNode<B> {
+    fn from(children: Children<B>) -> Self {
+        WidgetNode::Children(children)
+    }
 }
 
 impl<B: UiBackend> Clone for WidgetNode<B> {
@@ -283,6 +469,21 @@ impl<B: UiBackend> Clone for WidgetNode<B> {
                 children: Rc::clone(children),
             },
             WidgetNode::Group(g) => WidgetNode::Group(g.clone()),
+            WidgetNode::Portal { target, children } => WidgetNode::Portal {
+                target: target.clone(),
+                children: Rc::clone(children),
+            },
+            WidgetNode::Children(c) => WidgetNode::Children(c.clone()),
+            WidgetNode::Ref { node_ref, node } => WidgetNode::Ref {
+                node_ref: node_ref.clone(),
+                node: Rc::clone(node),
+            },
+            WidgetNode::Suspense { fallback, content } => WidgetNode::Suspense {
+                fallback: Rc::clone(fallback),
+                content: Rc::clone(content),
+            },
+            WidgetNode::Transition(t) => WidgetNode::Transition(t.clone()),
+            WidgetNode::Virtual(list) => WidgetNode::Virtual(list.clone()),
         }
     }
 }
@@ -325,9 +526,12 @@ struct MountedWidgetNodeGroup<B: UiBackend> {
     render_order: Vec<IntOrString>,
     ordered: Vec<MountedWidgetNode<B>>,
     named: HashMap<String, MountedWidgetNode<B>>,
+    /// Keys removed from the group that are still playing their exit
+    /// transition. They stay in `named` and `render_order` until they're done.
+    leaving: HashSet<String>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 pub enum IntOrString {
     Int(usize),
     String(String),
@@ -348,27 +552,74 @@ impl<B: UiBackend> MountedWidgetNodeGroup<B> {
             ordered: new_ordered,
             render_order: new_render_order,
         } = new.clone();
-        self.render_order = new_render_order;
+        let old_render_order = std::mem::replace(&mut self.render_order, new_render_order);
         if new_ordered.len() < self.ordered.len() {
             self.ordered
                 .drain(new_ordered.len()..)
                 .for_each(|w| w.unmount(ctx));
         }
 
+        let old_len = self.ordered.len();
         self.ordered
             .iter_mut()
-            .zip(new_ordered)
-            .for_each(|(old, new)| old.diff(&new, ctx));
+            .zip(&new_ordered)
+            .for_each(|(old, new)| old.diff(new, ctx));
+        for new in &new_ordered[old_len..] {
+            let mounted = new.mount(ctx);
+            self.ordered.push(mounted);
+        }
 
+        let leaving = &mut self.leaving;
         let ctx = RefCell::new(ctx);
         self.named
-            .drain_filter(|name, old| {
-                new_named
-                    .remove(name)
-                    .map(|new| old.diff(&new, *ctx.borrow_mut()))
-                    .is_none()
+            .drain_filter(|name, old| match new_named.remove(name) {
+                Some(new) => {
+                    if leaving.remove(name) {
+                        if let Some(t) = old.outer_transition() {
+                            t.set_leaving(false);
+                        }
+                    }
+                    old.diff(&new, *ctx.borrow_mut());
+                    false
+                }
+                None => match old.outer_transition() {
+                    Some(t) => {
+                        t.set_leaving(true);
+                        if !t.has_left() {
+                            leaving.insert(name.clone());
+                        }
+                        t.has_left()
+                    }
+                    None => true,
+                },
             })
             .for_each(|(_, w)| w.unmount(*ctx.borrow_mut()));
+        let ctx = ctx.into_inner();
+        for (name, new) in new_named {
+            let mounted = new.mount(ctx);
+            self.named.insert(name, mounted);
+        }
+
+        if !self.leaving.is_empty() {
+            self.keep_leaving_in_order(old_render_order);
+        }
+    }
+
+    /// Puts the keys that are leaving back into `render_order`, where they
+    /// were relative to the keys that stayed.
+    fn keep_leaving_in_order(&mut self, old_render_order: Vec<IntOrString>) {
+        let mut insert_at = 0;
+        for entry in old_render_order {
+            match self.render_order.iter().position(|e| *e == entry) {
+                Some(pos) => insert_at = pos + 1,
+                None => {
+                    if matches!(&entry, IntOrString::String(key) if self.leaving.contains(key)) {
+                        self.render_order.insert(insert_at, entry);
+                        insert_at += 1;
+                    }
+                }
+            }
+        }
     }
 
     fn process(&mut self, ctx: &mut InternalContext<B>) {
@@ -377,6 +628,29 @@ impl<B: UiBackend> MountedWidgetNodeGroup<B> {
             .map(|(_, v)| v)
             .chain(&mut self.ordered)
             .for_each(|node| node.process(ctx));
+
+        if self.leaving.is_empty() {
+            return;
+        }
+        let named = &mut self.named;
+        let left: Vec<String> = self
+            .leaving
+            .iter()
+            .filter(|key| {
+                !named
+                    .get_mut(*key)
+                    .is_some_and(MountedWidgetNode::is_leaving)
+            })
+            .cloned()
+            .collect();
+        for key in left {
+            self.leaving.remove(&key);
+            if let Some(node) = self.named.remove(&key) {
+                node.unmount(ctx);
+            }
+            let entry = IntOrString::String(key);
+            self.render_order.retain(|e| *e != entry);
+        }
     }
 }
 
@@ -385,13 +659,18 @@ impl<B: UiBackend> WidgetNodeGroup<B> {
         self.render_order.push(IntOrString::Int(self.ordered.len()));
         self.ordered.push(node);
     }
-    pub fn push_named(&mut self, node: WidgetNode<B>, name: impl Into<String>) {
+    pub fn push_named(
+        &mut self,
+        node: WidgetNode<B>,
+        name: impl Into<String>,
+    ) -> Result<(), DuplicateKeyError> {
         let name = name.into();
-        self.named
-            .insert(name.clone(), node)
-            .is_some()
-            .then(|| panic!("Same key used multiple times!"));
+        if self.named.contains_key(&name) {
+            return Err(DuplicateKeyError { key: name });
+        }
+        self.named.insert(name.clone(), node);
         self.render_order.push(IntOrString::String(name));
+        Ok(())
     }
 
     fn mount(&self, ctx: &mut InternalContext<B>) -> MountedWidgetNodeGroup<B> {
@@ -407,6 +686,7 @@ impl<B: UiBackend> WidgetNodeGroup<B> {
                 .collect(),
             ordered: ordered.into_iter().map(|w| w.mount(ctx)).collect(),
             render_order: render_order.clone(),
+            leaving: HashSet::new(),
         }
     }
 }
@@ -416,10 +696,29 @@ enum MountedWidgetNode<B: UiBackend> {
     Component(MountedWidgetComponent<B>),
     Effect(MountedWidgetEffectComponent<B>),
     Unit {
+        id: UnitId,
         unit: B::Unit,
         children: Box<MountedWidgetNode<B>>,
     },
     Group(MountedWidgetNodeGroup<B>),
+    Portal {
+        target: String,
+        children: Box<MountedWidgetNode<B>>,
+    },
+    Children {
+        source: Children<B>,
+        node: Box<MountedWidgetNode<B>>,
+    },
+    Ref {
+        node_ref: NodeRef<B>,
+        node: Box<MountedWidgetNode<B>>,
+    },
+    Suspense {
+        fallback: Box<MountedWidgetNode<B>>,
+        content: Box<MountedWidgetNode<B>>,
+    },
+    Transition(MountedTransition<B>),
+    Virtual(MountedVirtualList<B>),
 }
 
 impl<B: UiBackend> MountedWidgetNode<B> {
@@ -429,7 +728,7 @@ impl<B: UiBackend> MountedWidgetNode<B> {
             (MountedWidgetNode::Component(c), WidgetNode::Component(new)) => c.diff(new, ctx),
             (MountedWidgetNode::Effect(c), WidgetNode::Effect(new)) => c.diff(new, ctx),
             (
-                MountedWidgetNode::Unit { unit, children },
+                MountedWidgetNode::Unit { unit, children, .. },
                 WidgetNode::Unit {
                     unit: new_unit,
                     children: new_children,
@@ -439,6 +738,47 @@ impl<B: UiBackend> MountedWidgetNode<B> {
                 children.diff(&new_children, ctx);
             }
             (MountedWidgetNode::Group(old), WidgetNode::Group(new)) => old.diff(new, ctx),
+            (
+                MountedWidgetNode::Portal { target, children },
+                WidgetNode::Portal {
+                    target: new_target,
+                    children: new_children,
+                },
+            ) => {
+                target.clone_from(new_target);
+                children.diff(new_children, ctx);
+            }
+            (MountedWidgetNode::Children { source, node }, WidgetNode::Children(new)) => {
+                if *source != *new {
+                    node.diff(&new.0, ctx);
+                    *source = new.clone();
+                }
+            }
+            (
+                MountedWidgetNode::Ref { node_ref, node },
+                WidgetNode::Ref {
+                    node_ref: new_ref,
+                    node: new_node,
+                },
+            ) => {
+                if node_ref != new_ref {
+                    node_ref.set(None);
+                    *node_ref = new_ref.clone();
+                }
+                node.diff(new_node, ctx);
+            }
+            (
+                MountedWidgetNode::Suspense { fallback, content },
+                WidgetNode::Suspense {
+                    fallback: new_fallback,
+                    content: new_content,
+                },
+            ) => {
+                fallback.diff(new_fallback, ctx);
+                content.diff(new_content, ctx);
+            }
+            (MountedWidgetNode::Transition(t), WidgetNode::Transition(new)) => t.diff(new, ctx),
+            (MountedWidgetNode::Virtual(list), WidgetNode::Virtual(new)) => list.diff(new, ctx),
             (this, new) => std::mem::replace(this, new.mount(ctx)).unmount(ctx),
         }
     }
@@ -450,6 +790,18 @@ impl<B: UiBackend> MountedWidgetNode<B> {
             MountedWidgetNode::Effect(c) => c.unmount(ctx),
             MountedWidgetNode::Unit { children, .. } => children.unmount(ctx),
             MountedWidgetNode::Group(group) => group.unmount(ctx),
+            MountedWidgetNode::Portal { children, .. } => children.unmount(ctx),
+            MountedWidgetNode::Children { node, .. } => node.unmount(ctx),
+            MountedWidgetNode::Ref { node_ref, node } => {
+                node_ref.set(None);
+                node.unmount(ctx);
+            }
+            MountedWidgetNode::Suspense { fallback, content } => {
+                fallback.unmount(ctx);
+                content.unmount(ctx);
+            }
+            MountedWidgetNode::Transition(t) => t.unmount(ctx),
+            MountedWidgetNode::Virtual(list) => list.unmount(ctx),
         }
     }
 
@@ -460,27 +812,136 @@ impl<B: UiBackend> MountedWidgetNode<B> {
             MountedWidgetNode::Effect(c) => c.process(ctx, false),
             MountedWidgetNode::Unit { children, .. } => children.process(ctx),
             MountedWidgetNode::Group(group) => group.process(ctx),
+            MountedWidgetNode::Portal { children, .. } => children.process(ctx),
+            MountedWidgetNode::Children { node, .. } | MountedWidgetNode::Ref { node, .. } => {
+                node.process(ctx)
+            }
+            MountedWidgetNode::Suspense { fallback, content } => {
+                fallback.process(ctx);
+                content.process(ctx);
+            }
+            MountedWidgetNode::Transition(t) => t.process(ctx),
+            MountedWidgetNode::Virtual(list) => list.process(ctx),
         }
     }
 
-    fn render(&self) -> Vec<RenderNode<B>> {
+    fn render<'a>(
+        &'a self,
+        portals: &mut BTreeMap<&'a str, Vec<RenderNode<'a, B>>>,
+    ) -> Vec<RenderNode<'a, B>> {
         match self {
             MountedWidgetNode::None | MountedWidgetNode::Effect(_) => vec![],
-            MountedWidgetNode::Component(c) => c.result.render(),
-            MountedWidgetNode::Unit { unit, children } => vec![RenderNode {
+            MountedWidgetNode::Component(c) => c.result.render(portals),
+            MountedWidgetNode::Unit { id, unit, children } => vec![RenderNode {
+                id: *id,
                 unit,
-                children: children.render(),
+                children: children.render(portals),
+                node_ref: None,
             }],
             MountedWidgetNode::Group(g) => g
                 .render_order
                 .iter()
                 .flat_map(|ios| match ios {
-                    IntOrString::Int(i) => g.ordered[*i].render(),
-                    IntOrString::String(s) => g.named[s].render(),
+                    IntOrString::Int(i) => g.ordered[*i].render(portals),
+                    IntOrString::String(s) => g.named[s].render(portals),
                 })
                 .collect(),
+            MountedWidgetNode::Portal { target, children } => {
+                let rendered = children.render(portals);
+                portals.entry(target.as_str()).or_default().extend(rendered);
+                vec![]
+            }
+            MountedWidgetNode::Children { node, .. } => node.render(portals),
+            MountedWidgetNode::Ref { node_ref, node } => {
+                let mut rendered = node.render(portals);
+                match rendered.first_mut() {
+                    Some(first) => first.node_ref = Some(node_ref),
+                    None => node_ref.set(None),
+                }
+                rendered
+            }
+            MountedWidgetNode::Suspense { fallback, content } => {
+                if content.is_pending() {
+                    content.clear_refs();
+                    fallback.render(portals)
+                } else {
+                    fallback.clear_refs();
+                    content.render(portals)
+                }
+            }
+            MountedWidgetNode::Transition(t) => t.node.render(portals),
+            MountedWidgetNode::Virtual(list) => list.render(portals),
+        }
+    }
+
+    fn is_pending(&self) -> bool {
+        match self {
+            MountedWidgetNode::None | MountedWidgetNode::Effect(_) => false,
+            MountedWidgetNode::Component(c) => {
+                c.template.func.is_pending(&*c.init_data) || c.result.is_pending()
+            }
+            MountedWidgetNode::Unit { children, .. }
+            | MountedWidgetNode::Portal { children, .. } => children.is_pending(),
+            MountedWidgetNode::Group(g) => g
+                .named
+                .values()
+                .chain(&g.ordered)
+                .any(MountedWidgetNode::is_pending),
+            MountedWidgetNode::Children { node, .. } | MountedWidgetNode::Ref { node, .. } => {
+                node.is_pending()
+            }
+            MountedWidgetNode::Transition(t) => t.node.is_pending(),
+            MountedWidgetNode::Virtual(list) => list.is_pending(),
+            // A nested boundary takes care of its own content.
+            MountedWidgetNode::Suspense { fallback, content } => {
+                content.is_pending() && fallback.is_pending()
+            }
+        }
+    }
+
+    /// Empties the refs of everything in this node, for when it stays mounted
+    /// but isn't rendered.
+    fn clear_refs(&self) {
+        match self {
+            MountedWidgetNode::None | MountedWidgetNode::Effect(_) => {}
+            MountedWidgetNode::Component(c) => c.result.clear_refs(),
+            MountedWidgetNode::Unit { children, .. }
+            | MountedWidgetNode::Portal { children, .. } => children.clear_refs(),
+            MountedWidgetNode::Group(g) => g
+                .named
+                .values()
+                .chain(&g.ordered)
+                .for_each(MountedWidgetNode::clear_refs),
+            MountedWidgetNode::Children { node, .. } => node.clear_refs(),
+            MountedWidgetNode::Ref { node_ref, node } => {
+                node_ref.set(None);
+                node.clear_refs();
+            }
+            MountedWidgetNode::Suspense { fallback, content } => {
+                fallback.clear_refs();
+                content.clear_refs();
+            }
+            MountedWidgetNode::Transition(t) => t.node.clear_refs(),
+            MountedWidgetNode::Virtual(list) => list.clear_refs(),
+        }
+    }
+
+    /// The transition this node renders as, looking through components.
+    fn outer_transition(&mut self) -> Option<&mut MountedTransition<B>> {
+        match self {
+            MountedWidgetNode::Transition(t) => Some(t),
+            MountedWidgetNode::Component(c) => c.result.outer_transition(),
+            MountedWidgetNode::Children { node, .. } | MountedWidgetNode::Ref { node, .. } => {
+                node.outer_transition()
+            }
+            _ => None,
         }
     }
+
+    /// Whether a node removed from its group is still playing its exit.
+    fn is_leaving(&mut self) -> bool {
+        self.outer_transition().is_some_and(|t| !t.has_left())
+    }
 }
 
 struct MountedWidgetComponent<B: UiBackend> {
@@ -561,17 +1022,44 @@ impl<B: UiBackend> MountedWidgetEffectComponent<B> {
 
     fn unmount(self, ctx: &mut InternalContext<B>) {
         ctx.execute_at_end
-            .push(self.cleanup_fn.replace(Box::new(|_| {})))
+            .push(self.cleanup_fn.replace(Box::new(|_| {})));
+        let Self {
+            template,
+            init_data,
+            ..
+        } = self;
+        ctx.execute_at_end
+            .push(Box::new(move |ctx| template.func.deinit(ctx, init_data)));
     }
 }
 
 pub type DynDepList<B> = Vec<Box<dyn Fn(&<B as UiBackend>::RunCtx<'_>) -> bool>>;
 
 pub trait UiBackend: Sized + 'static {
-    type Unit: Clone;
+    type Unit: Clone + 'static;
     type RunCtx<'a>;
+    /// What a [`NodeRef`] points to once the unit has been rendered.
+    type NodeId: Copy + PartialEq + 'static;
 
     fn mark_update(ctx: &mut Self::RunCtx<'_>);
+    /// The current time, measured from any fixed point such as startup.
+    /// Drives time based params like [`Timer`]; a backend used in tests can
+    /// return a manually advanced clock.
+    fn now(ctx: &Self::RunCtx<'_>) -> Duration;
+
+    // Focus support, for backends with keyboard input. See [`FocusOrder`]
+    // and [`Focus`].
+    fn is_focusable(_unit: &Self::Unit) -> bool {
+        false
+    }
+    fn focused(_ctx: &Self::RunCtx<'_>) -> Option<Self::NodeId> {
+        None
+    }
+    fn set_focused(_ctx: &mut Self::RunCtx<'_>, _id: Option<Self::NodeId>) {}
+
+    /// Runs `f` on the [`StateSlots`] of the application, which the backend
+    /// keeps in the run context so [`SetState`] handles can reach them.
+    fn with_state_slots<R>(ctx: &mut Self::RunCtx<'_>, f: impl FnOnce(&mut StateSlots) -> R) -> R;
 
     // Store support
     type StoreId: Copy + 'static;
@@ -600,6 +1088,25 @@ pub trait UiBackend: Sized + 'static {
         ctx: &Self::RunCtx<'_>,
         init_data: &Self::StoreInitData,
     ) -> bool;
+
+    // Local store support, for values that aren't `Send` or `Sync`.
+    // These share `StoreId`, `StoreInitData` and `deinit_store` with regular stores.
+    type LocalTrackingPtr<'a, T: 'static>: DerefMut<Target = T>;
+    fn access_local_store_mut<'a, T: 'static>(
+        id: Self::StoreId,
+        ctx: &'a mut Self::RunCtx<'_>,
+    ) -> Self::LocalTrackingPtr<'a, T>;
+    fn access_local_store<'a, T: 'static>(id: Self::StoreId, ctx: &'a Self::RunCtx<'_>) -> &'a T;
+    fn read_local_store_marked<'a, T: 'static>(
+        id: Self::StoreId,
+        ctx: &'a Self::RunCtx<'_>,
+        init_data: &mut Self::StoreInitData,
+    ) -> &'a T;
+    fn init_local_store<T: 'static>(ctx: &mut Self::RunCtx<'_>, val: T) -> Self::StoreInitData;
+    fn check_local_store_needs_recalc<T: 'static>(
+        ctx: &Self::RunCtx<'_>,
+        init_data: &Self::StoreInitData,
+    ) -> bool;
 }
 
 pub struct Store<'a, T, B: UiBackend> {
@@ -675,6 +1182,78 @@ impl<B: UiBackend, T: Default + Send + Sync + 'static> WidgetParam<B> for Store<
     }
 }
 
+/// Like [`Store`], but for values that can't be sent across threads, such as
+/// `Rc` or `RefCell`.
+pub struct LocalStore<'a, T, B: UiBackend> {
+    val: &'a T,
+    id: B::StoreId,
+}
+
+impl<'a, T, B: UiBackend> LocalStore<'a, T, B> {
+    pub fn id(&self) -> LocalStoreId<T, B> {
+        LocalStoreId {
+            id: self.id,
+            _m: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, B: UiBackend> std::ops::Deref for LocalStore<'a, T, B> {
+    type Target = &'a T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.val
+    }
+}
+
+pub struct LocalStoreId<T, B: UiBackend> {
+    pub id: B::StoreId,
+    _m: PhantomData<T>,
+}
+
+impl<T: 'static, B: UiBackend> LocalStoreId<T, B> {
+    pub fn access_mut<'a>(self, ctx: &'a mut B::RunCtx<'_>) -> B::LocalTrackingPtr<'a, T> {
+        B::access_local_store_mut(self.id, ctx)
+    }
+}
+
+impl<T, B: UiBackend> Clone for LocalStoreId<T, B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, B: UiBackend> Copy for LocalStoreId<T, B> {}
+
+impl<B: UiBackend, T: Default + 'static> WidgetParam<B> for LocalStore<'static, T, B> {
+    type InitData = B::StoreInitData;
+
+    type Item<'ctx, 's> = LocalStore<'ctx, T, B>;
+
+    fn init(ctx: &mut B::RunCtx<'_>) -> B::StoreInitData {
+        B::init_local_store(ctx, T::default())
+    }
+
+    fn deinit(ctx: &mut B::RunCtx<'_>, init_data: Self::InitData) {
+        B::deinit_store(init_data, ctx)
+    }
+
+    fn get_item<'ctx, 's>(
+        ctx: &'ctx B::RunCtx<'_>,
+        init_data: &'s mut Self::InitData,
+    ) -> Self::Item<'ctx, 's> {
+        let id = B::id_from_store_init_data(init_data);
+        LocalStore {
+            val: B::read_local_store_marked(id, ctx, init_data),
+            id,
+        }
+    }
+
+    fn needs_recalc(ctx: &B::RunCtx<'_>, init_data: &Self::InitData) -> bool {
+        B::check_local_store_needs_recalc::<T>(ctx, init_data)
+    }
+}
+
 pub trait WidgetParam<B: UiBackend>: 'static {
     type InitData: 'static;
     type Item<'ctx, 's>;
@@ -686,6 +1265,11 @@ pub trait WidgetParam<B: UiBackend>: 'static {
         init_data: &'s mut Self::InitData,
     ) -> Self::Item<'ctx, 's>;
     fn needs_recalc(ctx: &B::RunCtx<'_>, init_data: &Self::InitData) -> bool;
+    /// Whether the param is waiting on something, which makes the closest
+    /// [`WidgetNode::Suspense`] show its fallback.
+    fn is_pending(_init_data: &Self::InitData) -> bool {
+        false
+    }
 }
 
 macro_rules! impl_functions {
@@ -737,6 +1321,11 @@ macro_rules! impl_functions {
                 false $(|| $params::needs_recalc(&ctx.backend_data, $params))*
             }
 
+            fn is_pending(&self, init_data: &dyn Any) -> bool {
+                let ($($params,)*): &($(<$params as WidgetParam<Backend>>::InitData,)*) = init_data.downcast_ref().unwrap();
+                false $(|| $params::is_pending($params))*
+            }
+
             fn init(&self, ctx: &mut InternalContext<Backend>) -> Box<dyn Any> {
                 Box::new(($($params::init(&mut ctx.backend_data),)*))
             }
@@ -764,3 +1353,172 @@ macro_rules! impl_functions {
 impl_functions!(
     _0, _1, _2, _3, _4, _5, _6, _7, _8, _9, _10, _11, _12, _13, _14, _15, _16, _17, _18, _19, _20
 );
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        any::{Any, TypeId},
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+
+    use crate::{
+        test_backend::{leaf, portals, set, unit, units, Setter, TestBackend, TestCtx},
+        Application, Children, DynEffectFunc, EffectFunc, State, WidgetFunc, WidgetNode,
+    };
+
+    #[derive(Default)]
+    struct EffectCounts {
+        runs: Cell<u32>,
+        cleanups: Cell<u32>,
+        deinits: Cell<u32>,
+    }
+
+    /// An effect that runs once and counts what happens to it.
+    #[derive(Clone, Default)]
+    struct Counted(Rc<EffectCounts>);
+
+    impl EffectFunc<(), TestBackend, ()> for Counted {
+        fn call(&self, _: &mut TestCtx, _: &(), _: &mut dyn Any) -> Box<dyn FnOnce(&mut TestCtx)> {
+            self.0.runs.set(self.0.runs.get() + 1);
+            let counts = Rc::clone(&self.0);
+            Box::new(move |_| counts.cleanups.set(counts.cleanups.get() + 1))
+        }
+
+        fn init(&self, _: &mut TestCtx) -> Rc<RefCell<dyn Any>> {
+            Rc::new(RefCell::new(()))
+        }
+
+        fn deinit(&self, _: &mut TestCtx, _: Rc<RefCell<dyn Any>>) {
+            self.0.deinits.set(self.0.deinits.get() + 1);
+        }
+
+        fn needs_recalc(&self, _: &TestCtx, _: &dyn Any) -> bool {
+            self.0.runs.get() == 0
+        }
+
+        fn as_dynamic(&self) -> Rc<dyn DynEffectFunc<TestBackend>> {
+            Rc::new(Box::new(self.clone()) as Box<dyn EffectFunc<(), TestBackend, ()>>)
+        }
+
+        fn fn_type_id(&self) -> TypeId {
+            TypeId::of::<Self>()
+        }
+    }
+
+    #[test]
+    fn unmount_runs_cleanups_and_deinit() {
+        let effect = Counted::default();
+        let mut ctx = TestCtx::default();
+        let app = Application::new(effect.clone().e(()), &mut ctx);
+        assert_eq!(effect.0.runs.get(), 1);
+        assert_eq!(effect.0.cleanups.get(), 0);
+
+        app.unmount(&mut ctx);
+        assert_eq!(effect.0.cleanups.get(), 1);
+        assert_eq!(effect.0.deinits.get(), 1);
+    }
+
+    #[test]
+    fn drop_without_unmount_leaks_quietly() {
+        let effect = Counted::default();
+        let mut ctx = TestCtx::default();
+        drop(Application::new(effect.clone().e(()), &mut ctx));
+        assert_eq!(effect.0.cleanups.get(), 0);
+        assert_eq!(effect.0.deinits.get(), 0);
+    }
+
+    fn portal(
+        target: &str,
+        children: impl Into<WidgetNode<TestBackend>>,
+    ) -> WidgetNode<TestBackend> {
+        WidgetNode::Portal {
+            target: target.to_string(),
+            children: Rc::new(children.into()),
+        }
+    }
+
+    fn with_portals(
+        setter: &Setter<bool>,
+        closed: State<bool, TestBackend>,
+    ) -> WidgetNode<TestBackend> {
+        setter.set(Some(closed.setter()));
+        let portals = if *closed {
+            WidgetNode::None
+        } else {
+            (
+                portal("overlay", (leaf("a"), leaf("b"))),
+                portal("modal", unit("m", leaf("m1"))),
+                portal("overlay", leaf("c")),
+            )
+                .into()
+        };
+        unit("root", (leaf("main"), portals))
+    }
+
+    #[test]
+    fn portals_render_under_their_target() {
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(with_portals.w((Rc::clone(&setter),)), &mut ctx);
+        assert_eq!(units(&app), ["root", "main"]);
+        assert_eq!(
+            portals(&app),
+            [
+                ("modal".to_string(), vec!["m".to_string(), "m1".to_string()]),
+                (
+                    "overlay".to_string(),
+                    vec!["a".to_string(), "b".to_string(), "c".to_string()]
+                ),
+            ]
+        );
+
+        set(&setter, &mut ctx, true);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["root", "main"]);
+        assert!(portals(&app).is_empty());
+        app.unmount(&mut ctx);
+    }
+
+    fn panel(children: &Children<TestBackend>) -> WidgetNode<TestBackend> {
+        unit("panel", children.clone())
+    }
+
+    fn child(
+        label: &u32,
+        setter: &Setter<u32>,
+        count: State<u32, TestBackend>,
+    ) -> WidgetNode<TestBackend> {
+        setter.set(Some(count.setter()));
+        leaf(&format!("{}:{}", label, *count))
+    }
+
+    fn with_children(
+        child_setter: &Setter<u32>,
+        setter: &Setter<u32>,
+        round: State<u32, TestBackend>,
+    ) -> WidgetNode<TestBackend> {
+        setter.set(Some(round.setter()));
+        let children = Children::new(child.w((*round, Rc::clone(child_setter))));
+        panel.w((children,))
+    }
+
+    #[test]
+    fn children_keep_state_across_parent_renders() {
+        let child_setter = Setter::default();
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(
+            with_children.w((Rc::clone(&child_setter), Rc::clone(&setter))),
+            &mut ctx,
+        );
+        set(&child_setter, &mut ctx, 5);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["panel", "0:5"]);
+
+        set(&setter, &mut ctx, 1);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["panel", "1:5"]);
+        app.unmount(&mut ctx);
+    }
+}
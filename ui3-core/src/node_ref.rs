@@ -0,0 +1,153 @@
+use std::{cell::Cell, rc::Rc};
+
+use crate::{UiBackend, WidgetNode, WidgetParam};
+
+/// A handle to the backend object a unit was rendered as, such as an entity.
+///
+/// Tag a node with [`WidgetNode::with_ref`], and the backend fills the handle
+/// in once it has applied the render. It's empty while the node isn't
+/// rendered, whether it's unmounted, renders nothing, or is hidden behind the
+/// fallback of a [`WidgetNode::Suspense`]. As a widget param, the same handle is kept for the whole lifetime
+/// of the component, so it can be read from effects and callbacks.
+pub struct NodeRef<B: UiBackend>(Rc<Cell<Option<B::NodeId>>>);
+
+impl<B: UiBackend> NodeRef<B> {
+    pub fn new() -> Self {
+        Self(Rc::new(Cell::new(None)))
+    }
+
+    pub fn get(&self) -> Option<B::NodeId> {
+        self.0.get()
+    }
+
+    /// Called by backends when applying a render.
+    pub fn set(&self, id: Option<B::NodeId>) {
+        self.0.set(id)
+    }
+}
+
+impl<B: UiBackend> Default for NodeRef<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: UiBackend> Clone for NodeRef<B> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<B: UiBackend> PartialEq for NodeRef<B> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<B: UiBackend> WidgetNode<B> {
+    /// Tags the first unit this node renders as with `node_ref`.
+    pub fn with_ref(self, node_ref: &NodeRef<B>) -> Self {
+        WidgetNode::Ref {
+            node_ref: node_ref.clone(),
+            node: Rc::new(self),
+        }
+    }
+}
+
+impl<B: UiBackend> WidgetParam<B> for NodeRef<B> {
+    type InitData = NodeRef<B>;
+    type Item<'ctx, 's> = NodeRef<B>;
+
+    fn init(_: &mut B::RunCtx<'_>) -> Self::InitData {
+        NodeRef::new()
+    }
+
+    fn deinit(_: &mut B::RunCtx<'_>, init_data: Self::InitData) {
+        init_data.set(None);
+    }
+
+    fn get_item<'ctx, 's>(
+        _: &'ctx B::RunCtx<'_>,
+        init_data: &'s mut Self::InitData,
+    ) -> Self::Item<'ctx, 's> {
+        init_data.clone()
+    }
+
+    fn needs_recalc(_: &B::RunCtx<'_>, _: &Self::InitData) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::NodeRef;
+    use crate::{
+        test_backend::{leaf, set, units, Setter, TestBackend, TestCtx},
+        Application, AsyncResource, State, WidgetFunc, WidgetNode,
+    };
+
+    type Refs = (NodeRef<TestBackend>, NodeRef<TestBackend>);
+
+    fn suspending(pending: &bool, mut res: AsyncResource<u32, ()>) -> WidgetNode<TestBackend> {
+        let pending = *pending;
+        res.load(pending, move || async move {
+            if pending {
+                std::future::pending::<()>().await;
+            }
+            Ok(0)
+        });
+        WidgetNode::None
+    }
+
+    fn tagged(
+        refs: &Refs,
+        setter: &Setter<bool>,
+        hidden: State<bool, TestBackend>,
+    ) -> WidgetNode<TestBackend> {
+        setter.set(Some(hidden.setter()));
+        let (shown, suspended) = refs;
+        let node = if *hidden {
+            WidgetNode::None
+        } else {
+            leaf("shown")
+        };
+        let content = (
+            leaf("content").with_ref(suspended),
+            suspending.w((*hidden,)),
+        );
+        let suspense = WidgetNode::Suspense {
+            fallback: Rc::new(leaf("fallback")),
+            content: Rc::new(content.into()),
+        };
+        (node.with_ref(shown), suspense).into()
+    }
+
+    #[test]
+    fn refs_are_empty_unless_rendered() {
+        let refs = Refs::default();
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(tagged.w((refs.clone(), Rc::clone(&setter))), &mut ctx);
+        assert_eq!(units(&app), ["shown", "content"]);
+        assert!(refs.0.get().is_some());
+        assert!(refs.1.get().is_some());
+
+        set(&setter, &mut ctx, true);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["fallback"]);
+        assert_eq!(refs.0.get(), None);
+        assert_eq!(refs.1.get(), None);
+
+        set(&setter, &mut ctx, false);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["shown", "content"]);
+        assert!(refs.0.get().is_some());
+        assert!(refs.1.get().is_some());
+
+        app.unmount(&mut ctx);
+        assert_eq!(refs.0.get(), None);
+        assert_eq!(refs.1.get(), None);
+    }
+}
@@ -0,0 +1,400 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+use crate::{UiBackend, WidgetParam};
+
+pub enum AsyncState<T, E> {
+    Pending,
+    Ready(T),
+    Failed(E),
+}
+
+impl<T, E> AsyncState<T, E> {
+    pub fn is_pending(&self) -> bool {
+        matches!(self, AsyncState::Pending)
+    }
+
+    pub fn ready(&self) -> Option<&T> {
+        match self {
+            AsyncState::Ready(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn failed(&self) -> Option<&E> {
+        match self {
+            AsyncState::Failed(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+struct WakeFlag(AtomicBool);
+
+impl Wake for WakeFlag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+type Task<T, E> = Pin<Box<dyn Future<Output = Result<T, E>>>>;
+
+pub struct AsyncResourceData<T, E> {
+    key: Option<Box<dyn Any>>,
+    task: Option<Task<T, E>>,
+    state: AsyncState<T, E>,
+    woken: Arc<WakeFlag>,
+}
+
+impl<T, E> AsyncResourceData<T, E> {
+    /// Polls the task if it was woken since the last poll, returning whether
+    /// it finished.
+    fn poll(&mut self) -> bool {
+        if !self.woken.0.swap(false, Ordering::Acquire) {
+            return false;
+        }
+        let task = match &mut self.task {
+            Some(task) => task,
+            None => return false,
+        };
+        let waker = Waker::from(Arc::clone(&self.woken));
+        match task.as_mut().poll(&mut Context::from_waker(&waker)) {
+            Poll::Pending => false,
+            Poll::Ready(result) => {
+                self.task = None;
+                self.state = match result {
+                    Ok(value) => AsyncState::Ready(value),
+                    Err(err) => AsyncState::Failed(err),
+                };
+                true
+            }
+        }
+    }
+}
+
+/// Runs a future on behalf of the component and keeps its result.
+///
+/// ```ignore
+/// fn level_list(mut levels: AsyncResource<Vec<Level>, io::Error>) -> WidgetNode {
+///     match levels.load("levels", || load_levels("assets/levels")) {
+///         AsyncState::Pending => view! { spinner() },
+///         AsyncState::Ready(levels) => ...,
+///         AsyncState::Failed(err) => ...,
+///     }
+/// }
+/// ```
+///
+/// The future is polled by [`Application::update`](crate::Application::update)
+/// whenever its waker fires, and the component re-renders once it resolves.
+/// It's dropped, cancelling it, when the component unmounts or a different
+/// key is passed to [`load`](Self::load). While the future is running, the
+/// closest [`WidgetNode::Suspense`](crate::WidgetNode::Suspense) shows its
+/// fallback.
+pub struct AsyncResource<'s, T, E> {
+    data: &'s mut RefCell<AsyncResourceData<T, E>>,
+}
+
+impl<'s, T, E> AsyncResource<'s, T, E> {
+    /// Starts the future returned by `make` unless the last call used the
+    /// same key, and returns the current state.
+    pub fn load<K, F>(&mut self, key: K, make: impl FnOnce() -> F) -> &AsyncState<T, E>
+    where
+        K: PartialEq + 'static,
+        F: Future<Output = Result<T, E>> + 'static,
+    {
+        let data = self.data.get_mut();
+        let same_key = data
+            .key
+            .as_ref()
+            .and_then(|old| old.downcast_ref::<K>())
+            .is_some_and(|old| *old == key);
+        if !same_key {
+            data.key = Some(Box::new(key));
+            data.task = Some(Box::pin(make()));
+            data.state = AsyncState::Pending;
+            data.woken.0.store(true, Ordering::Release);
+        }
+        data.poll();
+        &data.state
+    }
+
+    /// Makes the next [`load`](Self::load) start over, even with the same key.
+    pub fn restart(&mut self) {
+        self.data.get_mut().key = None;
+    }
+
+    pub fn state(&mut self) -> &AsyncState<T, E> {
+        &self.data.get_mut().state
+    }
+}
+
+impl<B: UiBackend, T: 'static, E: 'static> WidgetParam<B> for AsyncResource<'static, T, E> {
+    type InitData = RefCell<AsyncResourceData<T, E>>;
+    type Item<'ctx, 's> = AsyncResource<'s, T, E>;
+
+    fn init(_: &mut B::RunCtx<'_>) -> Self::InitData {
+        RefCell::new(AsyncResourceData {
+            key: None,
+            task: None,
+            state: AsyncState::Pending,
+            woken: Arc::new(WakeFlag(AtomicBool::new(false))),
+        })
+    }
+
+    fn deinit(_: &mut B::RunCtx<'_>, _: Self::InitData) {}
+
+    fn get_item<'ctx, 's>(
+        _: &'ctx B::RunCtx<'_>,
+        init_data: &'s mut Self::InitData,
+    ) -> Self::Item<'ctx, 's> {
+        AsyncResource { data: init_data }
+    }
+
+    fn needs_recalc(_: &B::RunCtx<'_>, init_data: &Self::InitData) -> bool {
+        init_data.borrow_mut().poll()
+    }
+
+    fn is_pending(init_data: &Self::InitData) -> bool {
+        init_data.borrow().task.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        future::Future,
+        pin::Pin,
+        rc::Rc,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::{AsyncResource, AsyncState};
+    use crate::{
+        test_backend::{leaf, set, units, Setter, TestBackend, TestCtx},
+        Application, SetState, State, WidgetFunc, WidgetNode,
+    };
+
+    /// A future only resolved by hand, which keeps the waker it's given.
+    #[derive(Default)]
+    struct Gate {
+        result: Cell<Option<u32>>,
+        waker: RefCell<Option<Waker>>,
+        dropped: Cell<bool>,
+    }
+
+    impl Gate {
+        fn resolve(&self, value: u32) {
+            self.result.set(Some(value));
+            if let Some(waker) = self.waker.borrow_mut().take() {
+                waker.wake();
+            }
+        }
+    }
+
+    struct GateFuture(Rc<Gate>);
+
+    impl Future for GateFuture {
+        type Output = Result<u32, ()>;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            match self.0.result.take() {
+                Some(value) => Poll::Ready(Ok(value)),
+                None => {
+                    *self.0.waker.borrow_mut() = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    impl Drop for GateFuture {
+        fn drop(&mut self) {
+            self.0.dropped.set(true);
+        }
+    }
+
+    /// Every future started so far, and the setter of the key they're loaded with.
+    #[derive(Default)]
+    struct Shared {
+        gates: RefCell<Vec<Rc<Gate>>>,
+        set_key: Cell<Option<SetState<u32, TestBackend>>>,
+    }
+
+    impl Shared {
+        fn gate(&self, index: usize) -> Rc<Gate> {
+            Rc::clone(&self.gates.borrow()[index])
+        }
+    }
+
+    fn loader(
+        key: &u32,
+        shared: &Rc<Shared>,
+        mut res: AsyncResource<u32, ()>,
+    ) -> WidgetNode<TestBackend> {
+        let state = res.load(*key, || {
+            let gate = Rc::new(Gate::default());
+            shared.gates.borrow_mut().push(Rc::clone(&gate));
+            GateFuture(gate)
+        });
+        let unit = match state {
+            AsyncState::Pending => "pending".to_string(),
+            AsyncState::Ready(value) => value.to_string(),
+            AsyncState::Failed(()) => "failed".to_string(),
+        };
+        WidgetNode::Unit {
+            unit,
+            children: Rc::new(WidgetNode::None),
+        }
+    }
+
+    fn keyed_loader(shared: &Rc<Shared>, key: State<u32, TestBackend>) -> WidgetNode<TestBackend> {
+        shared.set_key.set(Some(key.setter()));
+        loader.w((*key, Rc::clone(shared)))
+    }
+
+    fn mount() -> (Application<TestBackend>, TestCtx, Rc<Shared>) {
+        let shared = Rc::new(Shared::default());
+        let mut ctx = TestCtx::default();
+        let app = Application::new(keyed_loader.w((Rc::clone(&shared),)), &mut ctx);
+        (app, ctx, shared)
+    }
+
+    #[test]
+    fn pending_until_resolved() {
+        let (mut app, mut ctx, shared) = mount();
+        assert_eq!(units(&app), ["pending"]);
+
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["pending"]);
+
+        shared.gate(0).resolve(7);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["7"]);
+        assert_eq!(shared.gates.borrow().len(), 1);
+        app.unmount(&mut ctx);
+    }
+
+    #[test]
+    fn key_change_restarts_future() {
+        let (mut app, mut ctx, shared) = mount();
+        shared.gate(0).resolve(7);
+        app.update(&mut ctx);
+
+        let set_key = shared.set_key.get().unwrap();
+        set_key.set(&mut ctx, 1);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["pending"]);
+        assert_eq!(shared.gates.borrow().len(), 2);
+
+        // Switching keys again while the future runs drops it.
+        set_key.set(&mut ctx, 2);
+        app.update(&mut ctx);
+        assert!(shared.gate(1).dropped.get());
+        assert_eq!(shared.gates.borrow().len(), 3);
+
+        shared.gate(2).resolve(9);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["9"]);
+        app.unmount(&mut ctx);
+    }
+
+    #[test]
+    fn unmount_drops_future() {
+        let (app, mut ctx, shared) = mount();
+        let gate = shared.gate(0);
+        assert!(!gate.dropped.get());
+
+        app.unmount(&mut ctx);
+        assert!(gate.dropped.get());
+    }
+
+    fn suspense(
+        fallback: &str,
+        content: impl Into<WidgetNode<TestBackend>>,
+    ) -> WidgetNode<TestBackend> {
+        WidgetNode::Suspense {
+            fallback: Rc::new(leaf(fallback)),
+            content: Rc::new(content.into()),
+        }
+    }
+
+    fn counter(setter: &Setter<u32>, count: State<u32, TestBackend>) -> WidgetNode<TestBackend> {
+        setter.set(Some(count.setter()));
+        leaf(&format!("count{}", *count))
+    }
+
+    #[test]
+    fn suspense_shows_fallback_until_resolved() {
+        let shared = Rc::new(Shared::default());
+        let mut ctx = TestCtx::default();
+        let node = suspense("loading", (leaf("a"), loader.w((0, Rc::clone(&shared)))));
+        let mut app = Application::new(node, &mut ctx);
+        assert_eq!(units(&app), ["loading"]);
+
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["loading"]);
+
+        shared.gate(0).resolve(7);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["a", "7"]);
+        app.unmount(&mut ctx);
+    }
+
+    #[test]
+    fn suspended_content_keeps_state() {
+        let shared = Rc::new(Shared::default());
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let content = (
+            counter.w((Rc::clone(&setter),)),
+            keyed_loader.w((Rc::clone(&shared),)),
+        );
+        let mut app = Application::new(suspense("loading", content), &mut ctx);
+        set(&setter, &mut ctx, 5);
+        shared.gate(0).resolve(7);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["count5", "7"]);
+
+        // Suspending again leaves the counter and the loaded value alone.
+        shared.set_key.get().unwrap().set(&mut ctx, 1);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["loading"]);
+        set(&setter, &mut ctx, 6);
+        app.update(&mut ctx);
+
+        shared.gate(1).resolve(9);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["count6", "9"]);
+        assert_eq!(shared.gates.borrow().len(), 2);
+        app.unmount(&mut ctx);
+    }
+
+    #[test]
+    fn nested_suspense_uses_closest_boundary() {
+        let shared = Rc::new(Shared::default());
+        let mut ctx = TestCtx::default();
+        let inner = suspense("inner", loader.w((1, Rc::clone(&shared))));
+        let content = (leaf("a"), loader.w((0, Rc::clone(&shared))), inner);
+        let mut app = Application::new(suspense("outer", content), &mut ctx);
+        assert_eq!(units(&app), ["outer"]);
+
+        shared.gate(0).resolve(7);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["a", "7", "inner"]);
+
+        shared.gate(1).resolve(8);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["a", "7", "8"]);
+        app.unmount(&mut ctx);
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    any::Any,
+    cell::{Ref, RefCell},
+    marker::PhantomData,
+    mem,
+    rc::{Rc, Weak},
+};
+
+use crate::{UiBackend, WidgetParam};
+
+/// Where [`SetState`] handles find the state they belong to, while the state
+/// itself stays owned by the component.
+///
+/// Backends keep one next to the rest of the application's data in the run
+/// context, see [`UiBackend::with_state_slots`]. A slot is reused once its
+/// component is unmounted, under a new generation, so old handles to it do
+/// nothing.
+#[derive(Default)]
+pub struct StateSlots {
+    slots: Vec<(u32, Option<Weak<dyn Any>>)>,
+    free: Vec<usize>,
+}
+
+impl StateSlots {
+    fn insert(&mut self, cell: Weak<dyn Any>) -> (usize, u32) {
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.1 = Some(cell);
+                (index, slot.0)
+            }
+            None => {
+                self.slots.push((0, Some(cell)));
+                (self.slots.len() - 1, 0)
+            }
+        }
+    }
+
+    fn remove(&mut self, index: usize) {
+        let slot = &mut self.slots[index];
+        slot.0 = slot.0.wrapping_add(1);
+        slot.1 = None;
+        self.free.push(index);
+    }
+
+    fn get(&self, index: usize, generation: u32) -> Option<Rc<dyn Any>> {
+        match self.slots.get(index) {
+            Some((gen, Some(cell))) if *gen == generation => cell.upgrade(),
+            _ => None,
+        }
+    }
+}
+
+type Update<T> = Box<dyn FnOnce(&mut T)>;
+
+struct StateCell<T> {
+    value: RefCell<T>,
+    // Non-empty means the component needs to re-render.
+    pending: RefCell<Vec<Update<T>>>,
+}
+
+pub struct StateInitData<T> {
+    cell: Rc<StateCell<T>>,
+    index: usize,
+    generation: u32,
+}
+
+/// Component-local state kept by ui3-core itself, so it needs no backend
+/// support beyond [`StateSlots`] and costs no more than an allocation.
+///
+/// Changes go through the [`SetState`] handle, which is `Copy` and can be
+/// moved into any number of callbacks. They're applied right before the next
+/// render of the component.
+pub struct State<'s, T, B: UiBackend> {
+    value: Ref<'s, T>,
+    setter: SetState<T, B>,
+}
+
+impl<'s, T, B: UiBackend> State<'s, T, B> {
+    pub fn setter(&self) -> SetState<T, B> {
+        self.setter
+    }
+}
+
+impl<'s, T, B: UiBackend> std::ops::Deref for State<'s, T, B> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+pub struct SetState<T, B: UiBackend> {
+    index: usize,
+    generation: u32,
+    _m: PhantomData<fn(T, B)>,
+}
+
+impl<T: 'static, B: UiBackend> SetState<T, B> {
+    pub fn set(self, ctx: &mut B::RunCtx<'_>, value: T) {
+        self.update(ctx, move |v| *v = value)
+    }
+
+    /// Queues `f` to modify the state. Does nothing if the component has
+    /// been unmounted since.
+    pub fn update(self, ctx: &mut B::RunCtx<'_>, f: impl FnOnce(&mut T) + 'static) {
+        let cell = B::with_state_slots(ctx, |slots| slots.get(self.index, self.generation));
+        if let Some(cell) = cell.and_then(|cell| cell.downcast::<StateCell<T>>().ok()) {
+            cell.pending.borrow_mut().push(Box::new(f));
+        }
+    }
+}
+
+impl<T, B: UiBackend> Clone for SetState<T, B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, B: UiBackend> Copy for SetState<T, B> {}
+
+impl<T, B: UiBackend> PartialEq for SetState<T, B> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<B: UiBackend, T: Default + 'static> WidgetParam<B> for State<'static, T, B> {
+    type InitData = StateInitData<T>;
+    type Item<'ctx, 's> = State<'s, T, B>;
+
+    fn init(ctx: &mut B::RunCtx<'_>) -> Self::InitData {
+        let cell = Rc::new(StateCell {
+            value: RefCell::new(T::default()),
+            pending: RefCell::new(vec![]),
+        });
+        let weak = Rc::downgrade(&cell) as Weak<dyn Any>;
+        let (index, generation) = B::with_state_slots(ctx, |slots| slots.insert(weak));
+        StateInitData {
+            cell,
+            index,
+            generation,
+        }
+    }
+
+    fn deinit(ctx: &mut B::RunCtx<'_>, init_data: Self::InitData) {
+        B::with_state_slots(ctx, |slots| slots.remove(init_data.index));
+    }
+
+    fn get_item<'ctx, 's>(
+        _: &'ctx B::RunCtx<'_>,
+        init_data: &'s mut Self::InitData,
+    ) -> Self::Item<'ctx, 's> {
+        let pending = mem::take(&mut *init_data.cell.pending.borrow_mut());
+        if !pending.is_empty() {
+            let mut value = init_data.cell.value.borrow_mut();
+            pending.into_iter().for_each(|f| f(&mut value));
+        }
+        State {
+            value: init_data.cell.value.borrow(),
+            setter: SetState {
+                index: init_data.index,
+                generation: init_data.generation,
+                _m: PhantomData,
+            },
+        }
+    }
+
+    fn needs_recalc(_: &B::RunCtx<'_>, init_data: &Self::InitData) -> bool {
+        !init_data.cell.pending.borrow().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::State;
+    use crate::{
+        test_backend::{leaf, units, Setter, TestBackend, TestCtx},
+        Application, WidgetFunc, WidgetNode,
+    };
+
+    fn counter(setter: &Setter<u32>, count: State<u32, TestBackend>) -> WidgetNode<TestBackend> {
+        setter.set(Some(count.setter()));
+        leaf(&count.to_string())
+    }
+
+    #[test]
+    fn setter_moves_into_several_closures() {
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(counter.w((Rc::clone(&setter),)), &mut ctx);
+        let set = setter.get().unwrap();
+        let reset = move |ctx: &mut TestCtx| set.set(ctx, 0);
+        let add = move |ctx: &mut TestCtx, n| set.update(ctx, move |v| *v += n);
+
+        add(&mut ctx, 2);
+        add(&mut ctx, 3);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["5"]);
+
+        reset(&mut ctx);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["0"]);
+
+        app.unmount(&mut ctx);
+        add(&mut ctx, 1);
+    }
+
+    #[test]
+    fn stale_setter_misses_reused_slot() {
+        let (old, new) = (Setter::default(), Setter::default());
+        let mut ctx = TestCtx::default();
+        let app = Application::new(counter.w((Rc::clone(&old),)), &mut ctx);
+        app.unmount(&mut ctx);
+
+        let mut app = Application::new(counter.w((Rc::clone(&new),)), &mut ctx);
+        assert!(old.get() != new.get());
+        old.get().unwrap().set(&mut ctx, 7);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["0"]);
+        app.unmount(&mut ctx);
+    }
+}
@@ -0,0 +1,199 @@
+//! A backend for unit tests: units are strings, stores live in a `Vec` and
+//! the clock only moves when a test sets it.
+
+use std::{any::Any, cell::Cell, rc::Rc, time::Duration};
+
+use crate::{Application, RenderNode, SetState, StateSlots, UiBackend, UnitId, WidgetNode};
+
+pub struct TestBackend;
+
+#[derive(Default)]
+pub struct TestCtx {
+    pub now: Duration,
+    stores: Vec<Option<Box<dyn Any>>>,
+    state_slots: StateSlots,
+}
+
+pub struct StorePtr<'a, T>(&'a mut T);
+
+impl<'a, T> std::ops::Deref for StorePtr<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'a, T> std::ops::DerefMut for StorePtr<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0
+    }
+}
+
+impl TestCtx {
+    fn store<T: 'static>(&self, id: usize) -> &T {
+        self.stores[id].as_ref().unwrap().downcast_ref().unwrap()
+    }
+
+    fn store_mut<T: 'static>(&mut self, id: usize) -> StorePtr<'_, T> {
+        StorePtr(self.stores[id].as_mut().unwrap().downcast_mut().unwrap())
+    }
+
+    fn insert_store(&mut self, val: Box<dyn Any>) -> usize {
+        self.stores.push(Some(val));
+        self.stores.len() - 1
+    }
+}
+
+impl UiBackend for TestBackend {
+    type Unit = String;
+
+    type RunCtx<'a> = TestCtx;
+
+    type NodeId = UnitId;
+
+    fn mark_update(_: &mut Self::RunCtx<'_>) {}
+
+    fn now(ctx: &Self::RunCtx<'_>) -> Duration {
+        ctx.now
+    }
+
+    fn with_state_slots<R>(ctx: &mut Self::RunCtx<'_>, f: impl FnOnce(&mut StateSlots) -> R) -> R {
+        f(&mut ctx.state_slots)
+    }
+
+    type StoreId = usize;
+
+    type TrackingPtr<'a, T: Send + Sync + 'static> = StorePtr<'a, T>;
+
+    type StoreInitData = usize;
+
+    fn access_store_mut<'a, T: Send + Sync + 'static>(
+        id: Self::StoreId,
+        ctx: &'a mut Self::RunCtx<'_>,
+    ) -> Self::TrackingPtr<'a, T> {
+        ctx.store_mut(id)
+    }
+
+    fn access_store<'a, T: Send + Sync + 'static>(
+        id: Self::StoreId,
+        ctx: &'a Self::RunCtx<'_>,
+    ) -> &'a T {
+        ctx.store(id)
+    }
+
+    fn read_store_marked<'a, T: Send + Sync + 'static>(
+        id: Self::StoreId,
+        ctx: &'a Self::RunCtx<'_>,
+        _: &mut Self::StoreInitData,
+    ) -> &'a T {
+        ctx.store(id)
+    }
+
+    fn init_store<T: Send + Sync + 'static>(
+        ctx: &mut Self::RunCtx<'_>,
+        val: T,
+    ) -> Self::StoreInitData {
+        ctx.insert_store(Box::new(val))
+    }
+
+    fn deinit_store(data: Self::StoreInitData, ctx: &mut Self::RunCtx<'_>) {
+        ctx.stores[data] = None;
+    }
+
+    fn id_from_store_init_data(data: &Self::StoreInitData) -> Self::StoreId {
+        *data
+    }
+
+    fn check_store_needs_recalc<T: Send + Sync + 'static>(
+        _: &Self::RunCtx<'_>,
+        _: &Self::StoreInitData,
+    ) -> bool {
+        false
+    }
+
+    type LocalTrackingPtr<'a, T: 'static> = StorePtr<'a, T>;
+
+    fn access_local_store_mut<'a, T: 'static>(
+        id: Self::StoreId,
+        ctx: &'a mut Self::RunCtx<'_>,
+    ) -> Self::LocalTrackingPtr<'a, T> {
+        ctx.store_mut(id)
+    }
+
+    fn access_local_store<'a, T: 'static>(id: Self::StoreId, ctx: &'a Self::RunCtx<'_>) -> &'a T {
+        ctx.store(id)
+    }
+
+    fn read_local_store_marked<'a, T: 'static>(
+        id: Self::StoreId,
+        ctx: &'a Self::RunCtx<'_>,
+        _: &mut Self::StoreInitData,
+    ) -> &'a T {
+        ctx.store(id)
+    }
+
+    fn init_local_store<T: 'static>(ctx: &mut Self::RunCtx<'_>, val: T) -> Self::StoreInitData {
+        ctx.insert_store(Box::new(val))
+    }
+
+    fn check_local_store_needs_recalc<T: 'static>(
+        _: &Self::RunCtx<'_>,
+        _: &Self::StoreInitData,
+    ) -> bool {
+        false
+    }
+}
+
+/// Where a test component leaves its state setter, to change the state from
+/// outside.
+pub type Setter<T> = Rc<Cell<Option<SetState<T, TestBackend>>>>;
+
+pub fn set<T: 'static>(setter: &Setter<T>, ctx: &mut TestCtx, value: T) {
+    setter.get().unwrap().set(ctx, value);
+}
+
+/// `name` as a unit, with `children` under it.
+pub fn unit(name: &str, children: impl Into<WidgetNode<TestBackend>>) -> WidgetNode<TestBackend> {
+    WidgetNode::Unit {
+        unit: name.to_string(),
+        children: Rc::new(children.into()),
+    }
+}
+
+/// `name` as a unit without children.
+pub fn leaf(name: &str) -> WidgetNode<TestBackend> {
+    unit(name, WidgetNode::None)
+}
+
+/// Also fills in the refs of the units with their ids, as a backend applying
+/// the render would.
+fn walk(nodes: &[RenderNode<TestBackend>], out: &mut Vec<String>) {
+    for node in nodes {
+        if let Some(node_ref) = node.node_ref {
+            node_ref.set(Some(node.id));
+        }
+        out.push(node.unit.clone());
+        walk(&node.children, out);
+    }
+}
+
+/// The units of the main tree, depth first.
+pub fn units(app: &Application<TestBackend>) -> Vec<String> {
+    let mut out = vec![];
+    walk(&app.render().main, &mut out);
+    out
+}
+
+/// The units rendered under each portal target, depth first.
+pub fn portals(app: &Application<TestBackend>) -> Vec<(String, Vec<String>)> {
+    app.render()
+        .portals
+        .into_iter()
+        .map(|(target, nodes)| {
+            let mut out = vec![];
+            walk(&nodes, &mut out);
+            (target.to_string(), out)
+        })
+        .collect()
+}
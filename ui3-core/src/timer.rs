@@ -0,0 +1,237 @@
+use std::time::Duration;
+
+use crate::{UiBackend, WidgetParam};
+
+pub struct TimerData {
+    deadline: Option<Duration>,
+    finished: bool,
+}
+
+/// A one-shot timer that re-renders the component once it runs out, using the
+/// clock from [`UiBackend::now`].
+///
+/// ```ignore
+/// fn toast(text: &String, mut timer: Timer) -> WidgetNode {
+///     if !timer.is_running() && !timer.is_finished() {
+///         timer.start(Duration::from_secs(3));
+///     }
+///     if timer.is_finished() { WidgetNode::None } else { ... }
+/// }
+/// ```
+pub struct Timer<'s> {
+    data: &'s mut TimerData,
+    now: Duration,
+}
+
+impl<'s> Timer<'s> {
+    /// (Re)starts the timer, to run out `after` from now.
+    pub fn start(&mut self, after: Duration) {
+        self.data.deadline = Some(self.now + after);
+        self.data.finished = false;
+    }
+
+    pub fn cancel(&mut self) {
+        self.data.deadline = None;
+        self.data.finished = false;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.data.deadline.is_some()
+    }
+
+    /// Whether the timer ran out since it was last started.
+    pub fn is_finished(&self) -> bool {
+        self.data.finished
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        self.data
+            .deadline
+            .map(|deadline| deadline.checked_sub(self.now).unwrap_or_default())
+    }
+}
+
+impl<B: UiBackend> WidgetParam<B> for Timer<'static> {
+    type InitData = TimerData;
+    type Item<'ctx, 's> = Timer<'s>;
+
+    fn init(_: &mut B::RunCtx<'_>) -> Self::InitData {
+        TimerData {
+            deadline: None,
+            finished: false,
+        }
+    }
+
+    fn deinit(_: &mut B::RunCtx<'_>, _: Self::InitData) {}
+
+    fn get_item<'ctx, 's>(
+        ctx: &'ctx B::RunCtx<'_>,
+        init_data: &'s mut Self::InitData,
+    ) -> Self::Item<'ctx, 's> {
+        let now = B::now(ctx);
+        if init_data.deadline.is_some_and(|deadline| now >= deadline) {
+            init_data.deadline = None;
+            init_data.finished = true;
+        }
+        Timer {
+            data: init_data,
+            now,
+        }
+    }
+
+    fn needs_recalc(ctx: &B::RunCtx<'_>, init_data: &Self::InitData) -> bool {
+        init_data
+            .deadline
+            .is_some_and(|deadline| B::now(ctx) >= deadline)
+    }
+}
+
+pub struct IntervalData {
+    period: Option<Duration>,
+    start: Duration,
+    next: Duration,
+}
+
+/// Re-renders the component every time a period elapses, for things like
+/// blinking cursors or polling.
+///
+/// ```ignore
+/// fn cursor(mut blink: Interval) -> WidgetNode {
+///     let visible = blink.every(Duration::from_millis(500)) % 2 == 0;
+///     ...
+/// }
+/// ```
+pub struct Interval<'s> {
+    data: &'s mut IntervalData,
+    now: Duration,
+}
+
+impl<'s> Interval<'s> {
+    /// Keeps the interval running with the given period, and returns how
+    /// many periods have elapsed since it started. Changing the period starts
+    /// the count over.
+    pub fn every(&mut self, period: Duration) -> u64 {
+        if period == Duration::default() {
+            self.stop();
+            return 0;
+        }
+        if self.data.period != Some(period) {
+            self.data.period = Some(period);
+            self.data.start = self.now;
+        }
+        let elapsed = self.now.checked_sub(self.data.start).unwrap_or_default();
+        let ticks = elapsed.as_nanos() / period.as_nanos();
+        self.data.next =
+            self.data.start + Duration::from_nanos((period.as_nanos() * (ticks + 1)) as u64);
+        ticks as u64
+    }
+
+    pub fn stop(&mut self) {
+        self.data.period = None;
+    }
+}
+
+impl<B: UiBackend> WidgetParam<B> for Interval<'static> {
+    type InitData = IntervalData;
+    type Item<'ctx, 's> = Interval<'s>;
+
+    fn init(_: &mut B::RunCtx<'_>) -> Self::InitData {
+        IntervalData {
+            period: None,
+            start: Duration::default(),
+            next: Duration::default(),
+        }
+    }
+
+    fn deinit(_: &mut B::RunCtx<'_>, _: Self::InitData) {}
+
+    fn get_item<'ctx, 's>(
+        ctx: &'ctx B::RunCtx<'_>,
+        init_data: &'s mut Self::InitData,
+    ) -> Self::Item<'ctx, 's> {
+        Interval {
+            data: init_data,
+            now: B::now(ctx),
+        }
+    }
+
+    fn needs_recalc(ctx: &B::RunCtx<'_>, init_data: &Self::InitData) -> bool {
+        init_data.period.is_some() && B::now(ctx) >= init_data.next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc, time::Duration};
+
+    use super::{Interval, Timer};
+    use crate::{
+        test_backend::{TestBackend, TestCtx},
+        Application, WidgetFunc, WidgetNode,
+    };
+
+    type Log<T> = Rc<RefCell<Vec<T>>>;
+
+    fn toast(log: &Log<bool>, mut timer: Timer) -> WidgetNode<TestBackend> {
+        if !timer.is_running() && !timer.is_finished() {
+            timer.start(Duration::from_secs(3));
+        }
+        log.borrow_mut().push(timer.is_finished());
+        WidgetNode::None
+    }
+
+    fn ticker(log: &Log<u64>, mut interval: Interval) -> WidgetNode<TestBackend> {
+        log.borrow_mut()
+            .push(interval.every(Duration::from_secs(1)));
+        WidgetNode::None
+    }
+
+    fn advance(app: &mut Application<TestBackend>, ctx: &mut TestCtx, millis: u64) {
+        ctx.now = Duration::from_millis(millis);
+        app.update(ctx);
+    }
+
+    #[test]
+    fn timer_fires_once_at_deadline() {
+        let log = Log::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(toast.w((Rc::clone(&log),)), &mut ctx);
+        assert_eq!(*log.borrow(), [false]);
+
+        advance(&mut app, &mut ctx, 2999);
+        assert_eq!(*log.borrow(), [false]);
+
+        advance(&mut app, &mut ctx, 3000);
+        assert_eq!(*log.borrow(), [false, true]);
+
+        advance(&mut app, &mut ctx, 10_000);
+        assert_eq!(*log.borrow(), [false, true]);
+        app.unmount(&mut ctx);
+    }
+
+    #[test]
+    fn interval_counts_elapsed_periods() {
+        let log = Log::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(ticker.w((Rc::clone(&log),)), &mut ctx);
+        assert_eq!(*log.borrow(), [0]);
+
+        advance(&mut app, &mut ctx, 500);
+        assert_eq!(*log.borrow(), [0]);
+
+        advance(&mut app, &mut ctx, 1000);
+        assert_eq!(*log.borrow(), [0, 1]);
+
+        // Several periods passing between frames re-render once, with the
+        // count caught up.
+        advance(&mut app, &mut ctx, 4500);
+        assert_eq!(*log.borrow(), [0, 1, 4]);
+
+        advance(&mut app, &mut ctx, 4900);
+        assert_eq!(*log.borrow(), [0, 1, 4]);
+
+        advance(&mut app, &mut ctx, 5000);
+        assert_eq!(*log.borrow(), [0, 1, 4, 5]);
+        app.unmount(&mut ctx);
+    }
+}
@@ -0,0 +1,448 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Range,
+    rc::Rc,
+};
+
+use crate::{InternalContext, MountedWidgetNode, RenderNode, UiBackend, WidgetNode};
+
+/// How much room each item of a [`VirtualList`] takes along the scrolling
+/// axis.
+#[derive(Clone)]
+pub enum ItemExtent {
+    Fixed(f32),
+    /// One size per item, e.g. measured from a previous layout. Missing
+    /// entries count as zero.
+    Measured(Rc<[f32]>),
+}
+
+/// A list that only mounts the items intersecting its viewport, for lists too
+/// long to keep every item mounted.
+///
+/// ```ignore
+/// view! {
+///     {
+///         VirtualList::new(levels.len(), ItemExtent::Fixed(32.0), move |i| view! { level_row(levels[i].clone()) })
+///             .window(*scroll, 480.0)
+///             .key(move |i| levels[i].id.to_string())
+///             .spacer(|extent| view! { Unit::node(spacer_style(extent), Color::NONE) })
+///     }
+/// }
+/// ```
+///
+/// Items that stay in the window keep their mounted node, matched by key (or
+/// by index without [`key`](Self::key)). The nodes of items that scroll out
+/// are reused for the items scrolling in, under their new key, instead of
+/// being unmounted and mounted again, which means their params carry over
+/// too: per-item state belongs in props or stores. The room taken by the
+/// items that aren't mounted is filled with [`spacer`](Self::spacer) nodes,
+/// so the backend can lay out and scroll the list as a whole.
+pub struct VirtualList<B: UiBackend> {
+    len: usize,
+    extent: ItemExtent,
+    offset: f32,
+    viewport: f32,
+    overscan: usize,
+    render_item: Rc<dyn Fn(usize) -> WidgetNode<B>>,
+    key: Option<Rc<dyn Fn(usize) -> String>>,
+    spacer: Option<Rc<dyn Fn(f32) -> WidgetNode<B>>>,
+}
+
+impl<B: UiBackend> VirtualList<B> {
+    pub fn new(
+        len: usize,
+        extent: ItemExtent,
+        render_item: impl Fn(usize) -> WidgetNode<B> + 'static,
+    ) -> Self {
+        Self {
+            len,
+            extent,
+            offset: 0.0,
+            viewport: f32::INFINITY,
+            overscan: 2,
+            render_item: Rc::new(render_item),
+            key: None,
+            spacer: None,
+        }
+    }
+
+    /// The visible part of the list: how far it's scrolled and how long the
+    /// viewport is. Everything is mounted until this is set.
+    pub fn window(mut self, offset: f32, viewport: f32) -> Self {
+        self.offset = offset;
+        self.viewport = viewport;
+        self
+    }
+
+    /// How many items to keep mounted past each end of the viewport.
+    pub fn overscan(mut self, overscan: usize) -> Self {
+        self.overscan = overscan;
+        self
+    }
+
+    pub fn key(mut self, key: impl Fn(usize) -> String + 'static) -> Self {
+        self.key = Some(Rc::new(key));
+        self
+    }
+
+    /// Renders the room taken by the items before and after the window.
+    pub fn spacer(mut self, spacer: impl Fn(f32) -> WidgetNode<B> + 'static) -> Self {
+        self.spacer = Some(Rc::new(spacer));
+        self
+    }
+
+    fn key_of(&self, index: usize) -> String {
+        match &self.key {
+            Some(key) => key(index),
+            None => index.to_string(),
+        }
+    }
+
+    /// The items to mount, and the room taken by the items before and after.
+    fn visible(&self, measured: &mut Option<MeasuredStarts>) -> (Range<usize>, f32, f32) {
+        let end_offset = self.offset + self.viewport;
+        // An item is visible if it ends after the offset and starts before
+        // the end of the viewport.
+        match &self.extent {
+            ItemExtent::Fixed(size) if *size > 0.0 => {
+                let count = |items: f32| (items.max(0.0) as usize).min(self.len);
+                let first = count((self.offset / size).floor());
+                let last = count((end_offset / size).ceil());
+                self.with_overscan(first, last, |i| i as f32 * size)
+            }
+            // Empty items all end where the list starts.
+            ItemExtent::Fixed(_) => {
+                let first = if self.offset >= 0.0 { self.len } else { 0 };
+                let last = if end_offset > 0.0 { self.len } else { 0 };
+                self.with_overscan(first, last, |_| 0.0)
+            }
+            ItemExtent::Measured(sizes) => {
+                let starts = MeasuredStarts::get(measured, sizes, self.len);
+                let first = starts[1..].partition_point(|end| *end <= self.offset);
+                let last = starts[..self.len].partition_point(|start| *start < end_offset);
+                self.with_overscan(first, last, |i| starts[i])
+            }
+        }
+    }
+
+    /// Widens `first..last` by the overscan, and returns it along with the
+    /// room taken by the items before and after it.
+    fn with_overscan(
+        &self,
+        first: usize,
+        last: usize,
+        start_of: impl Fn(usize) -> f32,
+    ) -> (Range<usize>, f32, f32) {
+        let first = first.saturating_sub(self.overscan);
+        let last = (last + self.overscan).min(self.len).max(first);
+        (
+            first..last,
+            start_of(first),
+            start_of(self.len) - start_of(last),
+        )
+    }
+
+    fn spacer_node(&self, extent: f32) -> WidgetNode<B> {
+        match &self.spacer {
+            Some(spacer) if extent > 0.0 => spacer(extent),
+            _ => WidgetNode::None,
+        }
+    }
+}
+
+impl<B: UiBackend> Clone for VirtualList<B> {
+    fn clone(&self) -> Self {
+        Self {
+            len: self.len,
+            extent: self.extent.clone(),
+            offset: self.offset,
+            viewport: self.viewport,
+            overscan: self.overscan,
+            render_item: Rc::clone(&self.render_item),
+            key: self.key.clone(),
+            spacer: self.spacer.clone(),
+        }
+    }
+}
+
+impl<B: UiBackend> From<VirtualList<B>> for WidgetNode<B> {
+    fn from(list: VirtualList<B>) -> Self {
+        WidgetNode::Virtual(list)
+    }
+}
+
+/// Where each item of an [`ItemExtent::Measured`] list starts, plus where the
+/// last one ends. Kept across diffs for as long as the list is given the same
+/// sizes.
+struct MeasuredStarts {
+    sizes: Rc<[f32]>,
+    starts: Vec<f32>,
+}
+
+impl MeasuredStarts {
+    fn get<'a>(cache: &'a mut Option<Self>, sizes: &Rc<[f32]>, len: usize) -> &'a [f32] {
+        match cache {
+            Some(cached) if Rc::ptr_eq(&cached.sizes, sizes) && cached.starts.len() == len + 1 => {}
+            _ => {
+                let starts = std::iter::once(0.0)
+                    .chain((0..len).scan(0.0, |end, i| {
+                        *end += sizes.get(i).copied().unwrap_or(0.0);
+                        Some(*end)
+                    }))
+                    .collect();
+                *cache = Some(Self {
+                    sizes: Rc::clone(sizes),
+                    starts,
+                });
+            }
+        }
+        &cache.as_ref().unwrap().starts
+    }
+}
+
+pub(crate) struct MountedVirtualList<B: UiBackend> {
+    before: Box<MountedWidgetNode<B>>,
+    items: Vec<(String, MountedWidgetNode<B>)>,
+    after: Box<MountedWidgetNode<B>>,
+    measured: Option<MeasuredStarts>,
+}
+
+impl<B: UiBackend> MountedVirtualList<B> {
+    pub(crate) fn mount(template: &VirtualList<B>, ctx: &mut InternalContext<B>) -> Self {
+        let mut measured = None;
+        let (range, before, after) = template.visible(&mut measured);
+        Self {
+            before: Box::new(template.spacer_node(before).mount(ctx)),
+            items: range
+                .map(|i| (template.key_of(i), (template.render_item)(i).mount(ctx)))
+                .collect(),
+            after: Box::new(template.spacer_node(after).mount(ctx)),
+            measured,
+        }
+    }
+
+    pub(crate) fn diff(&mut self, new: &VirtualList<B>, ctx: &mut InternalContext<B>) {
+        let (range, before, after) = new.visible(&mut self.measured);
+        self.before.diff(&new.spacer_node(before), ctx);
+        self.after.diff(&new.spacer_node(after), ctx);
+
+        let keys: Vec<_> = range.clone().map(|i| new.key_of(i)).collect();
+        let mut staying = HashMap::new();
+        let mut recycled = vec![];
+        {
+            let wanted: HashSet<_> = keys.iter().collect();
+            for (key, node) in self.items.drain(..) {
+                if wanted.contains(&key) {
+                    if let Some(duplicate) = staying.insert(key, node) {
+                        recycled.push(duplicate);
+                    }
+                } else {
+                    recycled.push(node);
+                }
+            }
+        }
+
+        for (i, key) in range.zip(keys) {
+            let new_node = (new.render_item)(i);
+            let node = match staying.remove(&key).or_else(|| recycled.pop()) {
+                Some(mut node) => {
+                    node.diff(&new_node, ctx);
+                    node
+                }
+                None => new_node.mount(ctx),
+            };
+            self.items.push((key, node));
+        }
+        recycled
+            .into_iter()
+            .chain(staying.into_values())
+            .for_each(|node| node.unmount(ctx));
+    }
+
+    pub(crate) fn process(&mut self, ctx: &mut InternalContext<B>) {
+        self.before.process(ctx);
+        self.items
+            .iter_mut()
+            .for_each(|(_, node)| node.process(ctx));
+        self.after.process(ctx);
+    }
+
+    pub(crate) fn unmount(self, ctx: &mut InternalContext<B>) {
+        self.before.unmount(ctx);
+        self.items
+            .into_iter()
+            .for_each(|(_, node)| node.unmount(ctx));
+        self.after.unmount(ctx);
+    }
+
+    pub(crate) fn render<'a>(
+        &'a self,
+        portals: &mut BTreeMap<&'a str, Vec<RenderNode<'a, B>>>,
+    ) -> Vec<RenderNode<'a, B>> {
+        let mut rendered = self.before.render(portals);
+        for (_, node) in &self.items {
+            rendered.extend(node.render(portals));
+        }
+        rendered.extend(self.after.render(portals));
+        rendered
+    }
+
+    pub(crate) fn is_pending(&self) -> bool {
+        self.items.iter().any(|(_, node)| node.is_pending())
+    }
+
+    pub(crate) fn clear_refs(&self) {
+        self.before.clear_refs();
+        self.items.iter().for_each(|(_, node)| node.clear_refs());
+        self.after.clear_refs();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::{ItemExtent, VirtualList};
+    use crate::{
+        test_backend::{leaf, set, units, Setter, TestBackend, TestCtx},
+        Application, State, WidgetFunc, WidgetNode,
+    };
+
+    thread_local! {
+        static COUNTS: Cell<(u32, u32)> = const { Cell::new((0, 0)) };
+    }
+
+    /// Counts the rows mounted and unmounted on this thread.
+    struct Tracked;
+
+    impl Default for Tracked {
+        fn default() -> Self {
+            COUNTS.with(|c| c.set((c.get().0 + 1, c.get().1)));
+            Tracked
+        }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            COUNTS.with(|c| c.set((c.get().0, c.get().1 + 1)));
+        }
+    }
+
+    fn row(index: &usize, _tracked: State<Tracked, TestBackend>) -> WidgetNode<TestBackend> {
+        leaf(&index.to_string())
+    }
+
+    type ListFn = Rc<dyn Fn(f32) -> VirtualList<TestBackend>>;
+
+    fn scrolled(
+        setter: &Setter<f32>,
+        list: &ListFn,
+        offset: State<f32, TestBackend>,
+    ) -> WidgetNode<TestBackend> {
+        setter.set(Some(offset.setter()));
+        list(*offset).into()
+    }
+
+    fn list(len: usize, extent: ItemExtent, viewport: f32) -> ListFn {
+        Rc::new(move |offset| {
+            VirtualList::new(len, extent.clone(), |i| row.w((i,)))
+                .window(offset, viewport)
+                .overscan(1)
+                .spacer(|extent| leaf(&format!("spacer {}", extent)))
+        })
+    }
+
+    fn keyed(list: ListFn) -> ListFn {
+        Rc::new(move |offset| list(offset).key(|i| format!("k{}", i)))
+    }
+
+    #[test]
+    fn fixed_window() {
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let list = list(100, ItemExtent::Fixed(10.0), 30.0);
+        let mut app = Application::new(scrolled.w((Rc::clone(&setter), list)), &mut ctx);
+        assert_eq!(units(&app), ["0", "1", "2", "3", "spacer 960"]);
+
+        set(&setter, &mut ctx, 25.0);
+        app.update(&mut ctx);
+        assert_eq!(
+            units(&app),
+            ["spacer 10", "1", "2", "3", "4", "5", "6", "spacer 930"]
+        );
+
+        set(&setter, &mut ctx, 980.0);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["spacer 970", "97", "98", "99"]);
+        app.unmount(&mut ctx);
+    }
+
+    #[test]
+    fn measured_window() {
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let sizes = Rc::from(vec![10.0, 20.0, 30.0, 10.0, 10.0, 10.0, 10.0, 10.0]);
+        let list = list(8, ItemExtent::Measured(sizes), 20.0);
+        let mut app = Application::new(scrolled.w((Rc::clone(&setter), list)), &mut ctx);
+        assert_eq!(units(&app), ["0", "1", "2", "spacer 50"]);
+
+        set(&setter, &mut ctx, 25.0);
+        app.update(&mut ctx);
+        assert_eq!(units(&app), ["0", "1", "2", "3", "spacer 40"]);
+
+        set(&setter, &mut ctx, 65.0);
+        app.update(&mut ctx);
+        assert_eq!(
+            units(&app),
+            ["spacer 30", "2", "3", "4", "5", "6", "spacer 10"]
+        );
+        app.unmount(&mut ctx);
+    }
+
+    fn scroll_around(list: ListFn) {
+        let setter = Setter::default();
+        let mut ctx = TestCtx::default();
+        let mut app = Application::new(scrolled.w((Rc::clone(&setter), list)), &mut ctx);
+        assert_eq!(COUNTS.with(Cell::get), (4, 0));
+
+        set(&setter, &mut ctx, 25.0);
+        app.update(&mut ctx);
+        assert_eq!(COUNTS.with(Cell::get), (6, 0));
+
+        // Far enough that no item stays, the rows are all reused.
+        set(&setter, &mut ctx, 505.0);
+        app.update(&mut ctx);
+        assert_eq!(COUNTS.with(Cell::get), (6, 0));
+        assert_eq!(
+            units(&app),
+            [
+                "spacer 490",
+                "49",
+                "50",
+                "51",
+                "52",
+                "53",
+                "54",
+                "spacer 450"
+            ]
+        );
+
+        set(&setter, &mut ctx, 0.0);
+        app.update(&mut ctx);
+        assert_eq!(COUNTS.with(Cell::get), (6, 2));
+        assert_eq!(units(&app), ["0", "1", "2", "3", "spacer 960"]);
+
+        app.unmount(&mut ctx);
+        assert_eq!(COUNTS.with(Cell::get), (6, 6));
+    }
+
+    #[test]
+    fn scrolling_reuses_rows() {
+        scroll_around(list(100, ItemExtent::Fixed(10.0), 30.0));
+    }
+
+    #[test]
+    fn scrolling_reuses_keyed_rows() {
+        scroll_around(keyed(list(100, ItemExtent::Fixed(10.0), 30.0)));
+    }
+}
@@ -0,0 +1,254 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    spanned::Spanned,
+    Attribute, Expr, FnArg, Ident, ItemFn, Pat, Path, ReturnType, Token, Type,
+};
+
+use crate::parse_crate_path;
+
+pub struct ComponentArgs {
+    name: Option<Ident>,
+    krate: Path,
+}
+
+impl Parse for ComponentArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = if input.is_empty() || input.peek(Token![crate]) {
+            None
+        } else {
+            Some(input.parse()?)
+        };
+        if name.is_some() && !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+        let krate = parse_crate_path(input)?.unwrap_or_else(|| syn::parse_quote! { ::ui3_core });
+        if !input.is_empty() {
+            return Err(input.error("expected `crate = path`"));
+        }
+        Ok(Self { name, krate })
+    }
+}
+
+struct Prop {
+    ident: Ident,
+    pat: Box<Pat>,
+    ref_ty: Box<Type>,
+    ty: Type,
+    default: Option<Expr>,
+    into: bool,
+}
+
+impl Prop {
+    /// The type `new` and the setters take for this prop.
+    fn arg_ty(&self) -> TokenStream {
+        let ty = &self.ty;
+        if self.into {
+            quote! { impl ::std::convert::Into<#ty> }
+        } else {
+            quote! { #ty }
+        }
+    }
+}
+
+/// Parses the comma separated options of `#[prop(..)]`: `into`, `default`
+/// and `default = expr`.
+fn parse_prop_attr(attr: &Attribute, prop: &mut Prop) -> syn::Result<()> {
+    attr.parse_args_with(|input: ParseStream| {
+        while !input.is_empty() {
+            let ident: Ident = input.parse()?;
+            if ident == "into" {
+                prop.into = true;
+            } else if ident == "default" {
+                prop.default = Some(if input.peek(Token![=]) {
+                    input.parse::<Token![=]>()?;
+                    input.parse()?
+                } else {
+                    syn::parse_quote! { ::std::default::Default::default() }
+                });
+            } else {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "expected `into` or `default`",
+                ));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(())
+    })
+}
+
+fn pascal_case(ident: &Ident) -> Ident {
+    let name = ident
+        .to_string()
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect::<String>();
+    Ident::new(&name, ident.span())
+}
+
+pub fn expand(args: ComponentArgs, mut func: ItemFn) -> syn::Result<TokenStream> {
+    if !func.sig.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            func.sig.generics.span(),
+            "components can't be generic",
+        ));
+    }
+    let ret = match &func.sig.output {
+        ReturnType::Type(_, ty) => (**ty).clone(),
+        ReturnType::Default => {
+            return Err(syn::Error::new(
+                func.sig.span(),
+                "components must return a `WidgetNode`",
+            ))
+        }
+    };
+
+    let krate = &args.krate;
+    let vis = func.vis.clone();
+    let fn_name = func.sig.ident.clone();
+    let name = args.name.clone().unwrap_or_else(|| pascal_case(&fn_name));
+
+    // Reference arguments are props, everything else is a `WidgetParam`.
+    let mut props = vec![];
+    let mut params = vec![];
+    for arg in std::mem::take(&mut func.sig.inputs) {
+        let mut arg = match arg {
+            FnArg::Typed(arg) => arg,
+            FnArg::Receiver(recv) => {
+                return Err(syn::Error::new(recv.span(), "components can't take `self`"))
+            }
+        };
+        let ty = match &*arg.ty {
+            Type::Reference(r) => (*r.elem).clone(),
+            _ => {
+                params.push(FnArg::Typed(arg));
+                continue;
+            }
+        };
+        let ident = match &*arg.pat {
+            Pat::Ident(pat) => pat.ident.clone(),
+            pat => {
+                return Err(syn::Error::new(
+                    pat.span(),
+                    "props must be plain identifiers",
+                ))
+            }
+        };
+        let attrs = std::mem::take(&mut arg.attrs);
+        let mut prop = Prop {
+            ident,
+            pat: arg.pat,
+            ref_ty: arg.ty,
+            ty,
+            default: None,
+            into: false,
+        };
+        for attr in attrs {
+            if attr.path.is_ident("prop") {
+                parse_prop_attr(&attr, &mut prop)?;
+            } else {
+                arg.attrs.push(attr);
+            }
+        }
+        props.push(prop);
+    }
+
+    let props_ident = Ident::new("__ui3_props", Span::mixed_site());
+    func.sig
+        .inputs
+        .push(syn::parse_quote! { #props_ident: &#name });
+    func.sig.inputs.extend(params);
+
+    let body = &func.block;
+    let bindings = props.iter().map(
+        |Prop {
+             ident, pat, ref_ty, ..
+         }| {
+            quote! { let #pat: #ref_ty = &#props_ident.#ident; }
+        },
+    );
+    func.block = syn::parse_quote! {{
+        #(#bindings)*
+        #body
+    }};
+
+    let fields = props.iter().map(|Prop { ident, ty, .. }| {
+        quote! { #vis #ident: #ty }
+    });
+    let required = props.iter().filter(|p| p.default.is_none());
+    let new_args = required.clone().map(|prop| {
+        let ident = &prop.ident;
+        let arg_ty = prop.arg_ty();
+        quote! { #ident: #arg_ty }
+    });
+    let new_fields = props
+        .iter()
+        .map(|Prop { ident, default, .. }| match default {
+            Some(default) => quote! { #ident: #default },
+            None => quote! { #ident: #ident.into() },
+        });
+    let setters = props.iter().map(|prop| {
+        let ident = &prop.ident;
+        let arg_ty = prop.arg_ty();
+        let doc = format!("Sets the `{}` prop.", ident);
+        quote! {
+            #[doc = #doc]
+            #vis fn #ident(mut self, #ident: #arg_ty) -> Self {
+                self.#ident = #ident.into();
+                self
+            }
+        }
+    });
+    let default_impl = if required.clone().next().is_none() {
+        quote! {
+            impl ::std::default::Default for #name {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let struct_doc = format!("Props of the [`{}`] component.", fn_name);
+
+    Ok(quote! {
+        #[doc = #struct_doc]
+        #[non_exhaustive]
+        #vis struct #name {
+            #(#fields,)*
+        }
+
+        impl #name {
+            /// Creates the props from the required ones, with the rest set to their defaults.
+            #vis fn new(#(#new_args),*) -> Self {
+                Self {
+                    #(#new_fields,)*
+                }
+            }
+
+            #(#setters)*
+        }
+
+        #default_impl
+
+        impl ::std::convert::From<#name> for #ret {
+            fn from(props: #name) -> Self {
+                #krate::WidgetFunc::w(#fn_name, (props,))
+            }
+        }
+
+        #func
+    })
+}
@@ -0,0 +1,95 @@
+use proc_macro::TokenStream;
+
+use syn::{parse::ParseStream, Path, Token};
+
+mod component;
+mod view;
+
+/// Parses a leading `crate = path`, the path the generated code reaches
+/// `ui3_core` through.
+fn parse_crate_path(input: ParseStream) -> syn::Result<Option<Path>> {
+    if !(input.peek(Token![crate]) && input.peek2(Token![=])) {
+        return Ok(None);
+    }
+    input.parse::<Token![crate]>()?;
+    input.parse::<Token![=]>()?;
+    Ok(Some(Path::parse_mod_style(input)?))
+}
+
+/// Builds a `WidgetNode` tree.
+///
+/// ```ignore
+/// view! {
+///     node(style) {
+///         text("hi") {}
+///         if show_count { counter(todos.len()) }
+///         for t in todos key t.id {
+///             todo_item(t.clone())
+///         }
+///         { extra_node }
+///     }
+/// }
+/// ```
+///
+/// - `unit(args) { .. }` calls `unit(args)` to get a backend unit and mounts
+///   the nodes inside the braces as its children.
+/// - `func(args)` mounts the component `func` with the props `(args,)`.
+/// - `if` / `else` pick between node lists.
+/// - `for pat in iter { .. }` repeats its body, ordered by position. Adding
+///   `key expr` mounts the items keyed by `expr.to_string()` instead, so they
+///   keep their state as they move around. Keys are expected to be unique: a
+///   repeated key trips a debug assertion, and only the last item using it is
+///   kept.
+/// - `Props { field: value, .. }` mounts the component generated by
+///   [`macro@component`] for those props. The props struct is
+///   `#[non_exhaustive]`, so this only works in the crate defining the
+///   component; elsewhere use `{ Props::new(..).field(value) }`.
+/// - `{ expr }` inserts anything that converts into a `WidgetNode`.
+///
+/// Several nodes in the same list are wrapped in a `WidgetNode::Group`, an
+/// empty list becomes `WidgetNode::None`.
+///
+/// The generated code refers to `::ui3_core`. Starting the input with
+/// `crate = path;` uses `path` instead, which is how backends re-exporting
+/// `ui3_core` forward the macro.
+#[proc_macro]
+pub fn view(input: TokenStream) -> TokenStream {
+    syn::parse_macro_input!(input as view::View).expand().into()
+}
+
+/// Turns a widget function into a component with named props.
+///
+/// ```ignore
+/// #[component(TextW)]
+/// fn textw(
+///     #[prop(into)] text: &String,
+///     #[prop(default = 50.0)] size: &f32,
+///     font: UiRes<UiFont>,
+/// ) -> WidgetNode {
+///     ...
+/// }
+///
+/// let node: WidgetNode = TextW { text: "hi".into(), size: 30.0 }.into();
+/// let node: WidgetNode = TextW::new("hi").size(30.0).into();
+/// ```
+///
+/// Reference arguments become the fields of the props struct (named after the
+/// function in `PascalCase` unless given explicitly), the remaining arguments
+/// stay widget params. Props marked `#[prop(default)]` or
+/// `#[prop(default = expr)]` can be left out of `new`, and if all of them are,
+/// the struct also implements `Default`. Props marked `#[prop(into)]` accept
+/// anything that converts into them in `new` and their setter.
+///
+/// The struct is `#[non_exhaustive]`: other crates build it with `new`, the
+/// setters and `Default`, so adding a prop with a default doesn't break them.
+///
+/// Like [`view!`], the generated code refers to `::ui3_core` unless given
+/// `crate = path`, as in `#[component(TextW, crate = ui3_bevy::ui3_core)]`.
+#[proc_macro_attribute]
+pub fn component(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(attr as component::ComponentArgs);
+    let func = syn::parse_macro_input!(item as syn::ItemFn);
+    component::expand(args, func)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
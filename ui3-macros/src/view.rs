@@ -0,0 +1,251 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use syn::{
+    braced, parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    token, Expr, Pat, Path, Token,
+};
+
+use crate::parse_crate_path;
+
+/// The input of `view!`: the nodes, optionally preceded by `crate = path;`.
+pub struct View {
+    krate: Path,
+    nodes: ViewNodes,
+}
+
+impl Parse for View {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let krate = match parse_crate_path(input)? {
+            Some(krate) => {
+                input.parse::<Token![;]>()?;
+                krate
+            }
+            None => syn::parse_quote! { ::ui3_core },
+        };
+        Ok(Self {
+            krate,
+            nodes: input.parse()?,
+        })
+    }
+}
+
+impl View {
+    pub fn expand(&self) -> TokenStream {
+        self.nodes.expand(&self.krate)
+    }
+}
+
+struct ViewNodes(Vec<ViewNode>);
+
+enum ViewNode {
+    Unit {
+        func: Path,
+        args: Punctuated<Expr, Token![,]>,
+        children: ViewNodes,
+    },
+    Component {
+        func: Path,
+        args: Punctuated<Expr, Token![,]>,
+    },
+    Props {
+        ty: Path,
+        fields: TokenStream,
+    },
+    If {
+        cond: TokenStream,
+        then: ViewNodes,
+        otherwise: Option<Else>,
+    },
+    For {
+        pat: Box<Pat>,
+        iter: Box<Expr>,
+        key: Option<Box<Expr>>,
+        body: ViewNodes,
+    },
+    Expr(Expr),
+}
+
+enum Else {
+    If(Box<ViewNode>),
+    Block(ViewNodes),
+}
+
+impl Parse for ViewNodes {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut nodes = vec![];
+        while !input.is_empty() {
+            nodes.push(input.parse()?);
+        }
+        Ok(Self(nodes))
+    }
+}
+
+fn parse_braced_nodes(input: ParseStream) -> syn::Result<ViewNodes> {
+    let content;
+    braced!(content in input);
+    content.parse()
+}
+
+impl Parse for ViewNode {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![if]) {
+            input.parse::<Token![if]>()?;
+            // The condition is passed through untouched so `if let` works too.
+            let mut cond = TokenStream::new();
+            while !input.is_empty() && !input.peek(token::Brace) {
+                cond.extend(Some(input.parse::<TokenTree>()?));
+            }
+            if cond.is_empty() {
+                return Err(input.error("expected a condition"));
+            }
+            let then = parse_braced_nodes(input)?;
+            let otherwise = if input.peek(Token![else]) {
+                input.parse::<Token![else]>()?;
+                Some(if input.peek(Token![if]) {
+                    Else::If(Box::new(input.parse()?))
+                } else {
+                    Else::Block(parse_braced_nodes(input)?)
+                })
+            } else {
+                None
+            };
+            Ok(ViewNode::If {
+                cond,
+                then,
+                otherwise,
+            })
+        } else if input.peek(Token![for]) {
+            input.parse::<Token![for]>()?;
+            let pat = Box::new(input.parse()?);
+            input.parse::<Token![in]>()?;
+            let iter = Box::new(Expr::parse_without_eager_brace(input)?);
+            let key = match input.cursor().ident() {
+                Some((ident, _)) if ident == "key" => {
+                    input.parse::<TokenTree>()?;
+                    Some(Box::new(Expr::parse_without_eager_brace(input)?))
+                }
+                _ => None,
+            };
+            let body = parse_braced_nodes(input)?;
+            Ok(ViewNode::For {
+                pat,
+                iter,
+                key,
+                body,
+            })
+        } else if input.peek(token::Brace) {
+            let content;
+            braced!(content in input);
+            Ok(ViewNode::Expr(content.parse()?))
+        } else {
+            let func = input.parse()?;
+            let content;
+            if input.peek(token::Brace) {
+                braced!(content in input);
+                return Ok(ViewNode::Props {
+                    ty: func,
+                    fields: content.parse()?,
+                });
+            }
+            parenthesized!(content in input);
+            let args = content.parse_terminated(Expr::parse)?;
+            if input.peek(token::Brace) {
+                Ok(ViewNode::Unit {
+                    func,
+                    args,
+                    children: parse_braced_nodes(input)?,
+                })
+            } else {
+                Ok(ViewNode::Component { func, args })
+            }
+        }
+    }
+}
+
+impl ViewNodes {
+    fn expand(&self, krate: &Path) -> TokenStream {
+        match &self.0[..] {
+            [] => quote! { #krate::WidgetNode::None },
+            [node] => node.expand(krate),
+            nodes => {
+                let nodes = nodes.iter().map(|node| node.expand(krate));
+                quote! {{
+                    let mut __ui3_group = #krate::WidgetNodeGroup::default();
+                    #(__ui3_group.push(#nodes);)*
+                    #krate::WidgetNode::Group(__ui3_group)
+                }}
+            }
+        }
+    }
+}
+
+impl ViewNode {
+    fn expand(&self, krate: &Path) -> TokenStream {
+        match self {
+            ViewNode::Unit {
+                func,
+                args,
+                children,
+            } => {
+                let args = args.iter();
+                let children = children.expand(krate);
+                quote! {
+                    #krate::WidgetNode::Unit {
+                        unit: #func(#(#args),*),
+                        children: ::std::rc::Rc::new(#children),
+                    }
+                }
+            }
+            ViewNode::Component { func, args } => {
+                let args = args.iter();
+                quote! { #krate::WidgetFunc::w(#func, (#(#args,)*)) }
+            }
+            ViewNode::Props { ty, fields } => {
+                quote! { #krate::WidgetNode::from(#ty { #fields }) }
+            }
+            ViewNode::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                let then = then.expand(krate);
+                let otherwise = match otherwise {
+                    Some(Else::If(node)) => node.expand(krate),
+                    Some(Else::Block(nodes)) => nodes.expand(krate),
+                    None => quote! { #krate::WidgetNode::None },
+                };
+                quote! {
+                    if #cond { #then } else { #otherwise }
+                }
+            }
+            ViewNode::For {
+                pat,
+                iter,
+                key,
+                body,
+            } => {
+                let mut item = body.expand(krate);
+                if let Some(key) = key {
+                    // The key is taken first, the body may move out of the item.
+                    item = quote! {{
+                        let __ui3_key = ::std::string::ToString::to_string(&(#key));
+                        #krate::WidgetNode::keyed(#item, __ui3_key)
+                    }};
+                }
+                quote! {
+                    ::std::iter::Iterator::collect::<#krate::WidgetNode<_>>(
+                        ::std::iter::Iterator::map(
+                            ::std::iter::IntoIterator::into_iter(#iter),
+                            |#pat| #item,
+                        )
+                    )
+                }
+            }
+            ViewNode::Expr(expr) => {
+                quote! { #krate::WidgetNode::from(#expr) }
+            }
+        }
+    }
+}